@@ -10,7 +10,7 @@ pub struct FileDisplay {
     pub keywords: Vec<String>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Category {
     Text,
     Table,
@@ -28,6 +28,8 @@ impl ToString for FileID {
 impl FromStr for FileID {
     type Err = DecodeError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        general_purpose::URL_SAFE_NO_PAD.decode(s).map(|x| Self(u64::from_be_bytes(x.try_into().unwrap())))
+        general_purpose::URL_SAFE_NO_PAD.decode(s)
+            .and_then(|x| x.try_into().map_err(|_| DecodeError::InvalidLength))
+            .map(|x| Self(u64::from_be_bytes(x)))
     }
 }
\ No newline at end of file
@@ -0,0 +1,92 @@
+//! Summaries of a column's cells, shown under the table
+
+use std::collections::HashSet;
+
+use super::{column::ColumnKind, view::compare_values};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Sum,
+    Average,
+    Min,
+    Max,
+    /// Cells holding something
+    Count,
+    CountEmpty,
+    /// Different values, ignoring empty cells
+    CountUnique,
+}
+
+impl Aggregate {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aggregate::Sum => "sum",
+            Aggregate::Average => "average",
+            Aggregate::Min => "min",
+            Aggregate::Max => "max",
+            Aggregate::Count => "count",
+            Aggregate::CountEmpty => "count-empty",
+            Aggregate::CountUnique => "count-unique",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "sum" => Aggregate::Sum,
+            "average" => Aggregate::Average,
+            "min" => Aggregate::Min,
+            "max" => Aggregate::Max,
+            "count" => Aggregate::Count,
+            "count-empty" => Aggregate::CountEmpty,
+            "count-unique" => Aggregate::CountUnique,
+            _ => return None,
+        })
+    }
+
+    /// Summarizes the cells of a `kind` column, or `None` when that doesn't make sense for the kind.
+    /// Sums and averages are only for numbers, and like the minimum and maximum of numbers, skip cells that aren't numbers.
+    pub fn compute<'a>(&self, kind: &ColumnKind, values: impl IntoIterator<Item = Option<&'a str>>) -> Option<String> {
+        let values = values.into_iter()
+            .map(|v| v.map(str::trim).filter(|v| !v.is_empty()))
+            .collect::<Vec<_>>();
+        let filled = values.iter().flatten().copied();
+        Some(match self {
+            Aggregate::Count => filled.count().to_string(),
+            Aggregate::CountEmpty => values.iter().filter(|v| v.is_none()).count().to_string(),
+            Aggregate::CountUnique => filled.collect::<HashSet<_>>().len().to_string(),
+            Aggregate::Sum | Aggregate::Average => {
+                if kind != &ColumnKind::Number {
+                    return None;
+                }
+                let numbers = filled.filter_map(|v| v.parse::<f64>().ok()).collect::<Vec<_>>();
+                let sum = numbers.iter().sum::<f64>();
+                match self {
+                    Aggregate::Sum => format_number(sum),
+                    _ if numbers.is_empty() => return None,
+                    _ => format_number(sum / numbers.len() as f64),
+                }
+            },
+            Aggregate::Min | Aggregate::Max => {
+                let value = filled
+                    .filter(|v| kind != &ColumnKind::Number || v.parse::<f64>().is_ok())
+                    .reduce(|a, b| {
+                    let ordering = compare_values(kind, Some(a), Some(b));
+                    if ordering.is_gt() == (self == &Aggregate::Min) { b } else { a }
+                })?;
+                match (kind, value.parse::<f64>()) {
+                    (ColumnKind::Number, Ok(n)) => format_number(n),
+                    _ => value.to_string(),
+                }
+            },
+        })
+    }
+}
+
+/// Whole numbers without decimals, and others rounded to two decimal places
+pub fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        return (n as i64).to_string();
+    }
+    let n = format!("{:.2}", n);
+    n.trim_end_matches('0').trim_end_matches('.').to_string()
+}
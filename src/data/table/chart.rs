@@ -0,0 +1,50 @@
+//! Charts plotting a column of numbers against the labels of another column
+
+use super::column::ColumnID;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartKind {
+    Bar,
+    Line,
+    Pie,
+}
+
+impl ChartKind {
+    pub const ALL: [ChartKind; 3] = [ChartKind::Bar, ChartKind::Line, ChartKind::Pie];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChartKind::Bar => "bar",
+            ChartKind::Line => "line",
+            ChartKind::Pie => "pie",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "bar" => ChartKind::Bar,
+            "line" => ChartKind::Line,
+            "pie" => ChartKind::Pie,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chart {
+    pub kind: ChartKind,
+    /// The column naming each point
+    pub labels: ColumnID,
+    /// The column of numbers plotted
+    pub values: ColumnID,
+}
+
+/// The points to plot from the shown label and value of each row, leaving out the rows without a number
+pub fn chart_points<I: IntoIterator<Item = (Option<String>, Option<String>)>>(rows: I) -> Vec<(String, f64)> {
+    rows.into_iter()
+        .filter_map(|(label, value)| {
+            let value = value?.trim().parse::<f64>().ok().filter(|v| v.is_finite())?;
+            Some((label.unwrap_or_default(), value))
+        })
+        .collect()
+}
@@ -0,0 +1,41 @@
+//! Ranges of cells as spreadsheets put them on the clipboard, tab separated without a header
+
+use super::csv::{read_records, write_record};
+
+/// The rows of a range as tab separated values, with empty cells as empty fields
+pub fn range_to_tsv(rows: &[Vec<Option<String>>]) -> String {
+    let mut tsv = String::new();
+    for row in rows {
+        write_record(&mut tsv, row.iter().map(|c| c.as_deref().filter(|c| !c.is_empty())), '\t');
+    }
+    tsv
+}
+
+/// The rows of a range as an HTML table, which spreadsheets paste with its layout
+pub fn range_to_html(rows: &[Vec<Option<String>>]) -> String {
+    let mut html = String::from("<table>\n");
+    for row in rows {
+        html.push_str("<tr>");
+        for cell in row {
+            html.push_str("<td>");
+            html.push_str(&escape_html(cell.as_deref().unwrap_or("")).replace('\n', "<br>"));
+            html.push_str("</td>");
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+/// Reads the rows of a range copied from a spreadsheet
+pub fn range_from_tsv(tsv: &str) -> Vec<Vec<Option<String>>> {
+    let tsv = tsv.strip_prefix('\u{feff}').unwrap_or(tsv);
+    read_records(tsv, '\t')
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -1,6 +1,13 @@
 use std::str::FromStr;
 
 use base64::{engine::general_purpose, DecodeError, Engine};
+use rand::random;
+
+use crate::data::FileID;
+
+use super::aggregate::Aggregate;
+use super::formula::is_formula;
+use super::row::RowID;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ColumnID(u64);
@@ -14,7 +21,9 @@ impl ToString for ColumnID {
 impl FromStr for ColumnID {
     type Err = DecodeError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        general_purpose::URL_SAFE_NO_PAD.decode(s).map(|x| Self(u64::from_be_bytes(x.try_into().unwrap())))
+        general_purpose::URL_SAFE_NO_PAD.decode(s)
+            .and_then(|x| x.try_into().map_err(|_| DecodeError::InvalidLength))
+            .map(|x| Self(u64::from_be_bytes(x)))
     }
 }
 
@@ -23,4 +32,185 @@ pub struct Column {
     pub id: ColumnID,
     pub name: String,
     pub unique: bool,
-}
\ No newline at end of file
+    pub kind: ColumnKind,
+    /// What the footer shows under the column
+    pub aggregate: Option<Aggregate>,
+}
+
+/// What a column's cells hold. Cells are always stored as text,
+/// in the form [`ColumnKind::check`] gives them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ColumnKind {
+    #[default]
+    Text,
+    Number,
+    /// Stored as `YYYY-MM-DD`
+    Date,
+    /// Stored as `true` or `false`
+    Boolean,
+    Select(Vec<String>),
+    /// Stored one option per line, in the options' order
+    MultiSelect(Vec<String>),
+    Url,
+    /// Links to rows of another table, stored one row ID per line
+    Relation(FileID),
+    /// Shows the rows of another table linking to each row, through that table's relation columns.
+    /// Its cells hold nothing.
+    Rollup(FileID),
+}
+impl Column {
+    pub fn new(name: String) -> Self {
+        Self {
+            id: ColumnID(random::<u64>()),
+            name,
+            unique: false,
+            kind: ColumnKind::Text,
+            aggregate: None,
+        }
+    }
+}
+
+impl ColumnKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColumnKind::Text => "text",
+            ColumnKind::Number => "number",
+            ColumnKind::Date => "date",
+            ColumnKind::Boolean => "boolean",
+            ColumnKind::Select(_) => "select",
+            ColumnKind::MultiSelect(_) => "multi-select",
+            ColumnKind::Url => "url",
+            ColumnKind::Relation(_) => "relation",
+            ColumnKind::Rollup(_) => "rollup",
+        }
+    }
+
+    /// The kind called `name`, with `options` for the select kinds and `table` for the kinds linking tables
+    pub fn from_name(name: &str, options: Vec<String>, table: Option<FileID>) -> Option<Self> {
+        Some(match name {
+            "text" => ColumnKind::Text,
+            "number" => ColumnKind::Number,
+            "date" => ColumnKind::Date,
+            "boolean" => ColumnKind::Boolean,
+            "select" => ColumnKind::Select(options),
+            "multi-select" => ColumnKind::MultiSelect(options),
+            "url" => ColumnKind::Url,
+            "relation" => ColumnKind::Relation(table?),
+            "rollup" => ColumnKind::Rollup(table?),
+            _ => return None,
+        })
+    }
+
+    /// The table a relation or rollup column links with
+    pub fn table(&self) -> Option<FileID> {
+        match self {
+            ColumnKind::Relation(table) | ColumnKind::Rollup(table) => Some(*table),
+            _ => None,
+        }
+    }
+
+    pub fn options(&self) -> &[String] {
+        match self {
+            ColumnKind::Select(options) | ColumnKind::MultiSelect(options) => options,
+            _ => &[],
+        }
+    }
+
+    /// The value as it's stored in cells of this kind, or `None` if it isn't valid for it.
+    /// Text and number cells can also hold formulas.
+    pub fn check(&self, value: &str) -> Option<String> {
+        let trimmed = value.trim();
+        match self {
+            ColumnKind::Text => Some(value.to_string()),
+            ColumnKind::Number if is_formula(trimmed) => Some(trimmed.to_string()),
+            ColumnKind::Number => trimmed.parse::<f64>().ok()
+                .filter(|n| n.is_finite())
+                .map(|_| trimmed.to_string()),
+            ColumnKind::Date => parse_date(trimmed)
+                .map(|(y, m, d)| format!("{:04}-{:02}-{:02}", y, m, d)),
+            ColumnKind::Boolean => match trimmed.to_lowercase().as_str() {
+                "true" | "yes" | "1" => Some("true".to_string()),
+                "false" | "no" | "0" => Some("false".to_string()),
+                _ => None,
+            },
+            ColumnKind::Select(options) => find_option(options, trimmed).map(|o| o.to_string()),
+            ColumnKind::MultiSelect(options) => {
+                let mut chosen = Vec::new();
+                for value in trimmed.lines().map(str::trim).filter(|v| !v.is_empty()) {
+                    chosen.push(find_option(options, value)?);
+                }
+                Some(options.iter()
+                    .map(|o| o.as_str())
+                    .filter(|o| chosen.contains(o))
+                    .collect::<Vec<_>>()
+                    .join("\n"))
+            },
+            ColumnKind::Url => if is_url(trimmed) {
+                Some(trimmed.to_string())
+            } else if trimmed.starts_with("www.") && !trimmed.contains(char::is_whitespace) {
+                Some("https://".to_string() + trimmed)
+            } else {
+                None
+            },
+            ColumnKind::Relation(_) => {
+                let mut rows = Vec::new();
+                for line in trimmed.lines().map(str::trim).filter(|l| !l.is_empty()) {
+                    let row = line.parse::<RowID>().ok()?;
+                    if !rows.contains(&row) {
+                        rows.push(row);
+                    }
+                }
+                Some(rows.iter().map(|r| r.to_string()).collect::<Vec<_>>().join("\n"))
+            },
+            ColumnKind::Rollup(_) => None,
+        }
+    }
+}
+
+fn find_option<'a>(options: &'a [String], value: &str) -> Option<&'a str> {
+    options.iter()
+        .find(|o| o.as_str() == value)
+        .or_else(|| options.iter().find(|o| o.to_lowercase() == value.to_lowercase()))
+        .map(|o| o.as_str())
+}
+
+/// Year, month and day of an ISO 8601 calendar date
+pub fn parse_date(date: &str) -> Option<(i32, u32, u32)> {
+    let mut parts = date.splitn(3, '-');
+    let y = parts.next()?;
+    let m = parts.next()?;
+    let d = parts.next()?;
+    if y.len() != 4 || m.is_empty() || m.len() > 2 || d.is_empty() || d.len() > 2 {
+        return None;
+    }
+    let all_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if !all_digits(y) || !all_digits(m) || !all_digits(d) {
+        return None;
+    }
+    let (y, m, d) = (y.parse::<i32>().ok()?, m.parse::<u32>().ok()?, d.parse::<u32>().ok()?);
+    let leap = y % 4 == 0 && (y % 100 != 0 || y % 400 == 0);
+    let days = match m {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return None,
+    };
+    if d == 0 || d > days {
+        return None;
+    }
+    Some((y, m, d))
+}
+
+/// Whether `url` has a scheme followed by something, like `https://example.com` or `mailto:a@b.c`
+fn is_url(url: &str) -> bool {
+    match url.split_once(':') {
+        Some((scheme, rest)) => {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+.-".contains(c))
+                && !rest.is_empty()
+                && !url.contains(char::is_whitespace)
+        },
+        None => false,
+    }
+}
@@ -0,0 +1,135 @@
+//! Delimiter separated values, like CSV and TSV.
+//! Missing cells are written as empty fields and empty cells as `""`, so both survive a round trip.
+
+use super::{TableContent, TableCell, column::Column};
+
+const DELIMITERS: [char; 4] = [',', '\t', ';', '|'];
+
+impl TableContent {
+    /// The table with its column names as the header row
+    pub fn to_csv(&self, delimiter: char) -> String {
+        let mut csv = String::new();
+        let names = self.columns.iter().map(|c| Some(c.name.as_str()));
+        write_record(&mut csv, names, delimiter);
+        if !self.columns.is_empty() {
+            for row in self.cells.chunks(self.columns.len()) {
+                write_record(&mut csv, row.iter().map(|c| c.content.as_deref()), delimiter);
+            }
+        }
+        csv
+    }
+
+    /// Reads the first record as column names, and the rest as rows.
+    /// Short rows are filled with missing cells, and long ones get extra columns.
+    pub fn from_csv(csv: &str, delimiter: char) -> Self {
+        let csv = csv.strip_prefix('\u{feff}').unwrap_or(csv);
+        let mut records = read_records(csv, delimiter).into_iter();
+        let mut columns = records.next()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|name| Column::new(name.unwrap_or_default()))
+            .collect::<Vec<_>>();
+        let rows = records.collect::<Vec<_>>();
+        let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+        while columns.len() < width {
+            columns.push(Column::new(format!("Column {}", columns.len() + 1)));
+        }
+        let mut cells = Vec::with_capacity(rows.len() * columns.len());
+        for row in rows {
+            let missing = columns.len() - row.len();
+            cells.extend(row.into_iter().map(|content| TableCell { content }));
+            cells.extend((0..missing).map(|_| TableCell { content: None }));
+        }
+        let mut table = Self { columns, cells, ..Default::default() };
+        table.fill_row_ids();
+        table
+    }
+}
+
+/// Guesses the delimiter from the first record, preferring commas
+pub fn detect_delimiter(csv: &str) -> char {
+    let mut counts = [0; DELIMITERS.len()];
+    let mut quoted = false;
+    for c in csv.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '\n' | '\r' if !quoted => break,
+            c if !quoted => if let Some(i) = DELIMITERS.iter().position(|d| *d == c) {
+                counts[i] += 1;
+            },
+            _ => (),
+        }
+    }
+    let (i, count) = counts.iter().enumerate()
+        .fold((0, 0), |best, (i, count)| if *count > best.1 { (i, *count) } else { best });
+    if count == 0 {
+        DELIMITERS[0]
+    } else {
+        DELIMITERS[i]
+    }
+}
+
+pub(super) fn write_record<'a>(csv: &mut String, fields: impl Iterator<Item = Option<&'a str>>, delimiter: char) {
+    for (i, field) in fields.enumerate() {
+        if i != 0 {
+            csv.push(delimiter);
+        }
+        match field {
+            None => (),
+            Some(field) if field.is_empty() || field.contains([delimiter, '"', '\n', '\r']) => {
+                csv.push('"');
+                csv.push_str(&field.replace('"', "\"\""));
+                csv.push('"');
+            },
+            Some(field) => csv.push_str(field),
+        }
+    }
+    csv.push_str("\r\n");
+}
+
+/// Splits the text into records of fields, where an unquoted empty field is `None`
+pub(super) fn read_records(csv: &str, delimiter: char) -> Vec<Vec<Option<String>>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    // Whether the current field had quotes, so `""` can be told apart from nothing
+    let mut was_quoted = false;
+    let mut chars = csv.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if field.is_empty() && !was_quoted => {
+                was_quoted = true;
+                while let Some(c) = chars.next() {
+                    if c == '"' {
+                        if chars.peek() == Some(&'"') {
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    field.push(c);
+                }
+            },
+            c if c == delimiter => end_field(&mut field, &mut was_quoted, &mut record),
+            '\r' | '\n' => {
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                end_field(&mut field, &mut was_quoted, &mut record);
+                records.push(std::mem::take(&mut record));
+            },
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || was_quoted || !record.is_empty() {
+        end_field(&mut field, &mut was_quoted, &mut record);
+        records.push(record);
+    }
+    records
+}
+
+fn end_field(field: &mut String, was_quoted: &mut bool, record: &mut Vec<Option<String>>) {
+    let content = std::mem::take(field);
+    record.push(if content.is_empty() && !*was_quoted { None } else { Some(content) });
+    *was_quoted = false;
+}
@@ -0,0 +1,766 @@
+//! Cells starting with `=` hold formulas, like `=SUM(B:B) / COUNT(B:B)` or `=[Price] * [Amount]`.
+//!
+//! Cells are referred to by column letter and row number (`A1`), or by column name (`[Price]3`).
+//! A column name alone means the cell in the formula's own row.
+//! Ranges go between two cells (`A1:B3`), or take whole columns (`A:B`, `[Price]:[Price]`).
+
+use std::{collections::{HashMap, HashSet}, fmt::{self, Display}};
+
+use super::{CellRef, TableContent, column::ColumnID};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Empty,
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    Error(FormulaError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormulaError {
+    /// The formula couldn't be parsed
+    Syntax,
+    /// Unknown function or name
+    Name,
+    /// A reference to a column or row that doesn't exist
+    Ref,
+    /// A value of the wrong type
+    Value,
+    DivZero,
+    /// The formula depends on its own value
+    Cycle,
+    /// The formula nests too deeply to be worked out
+    Depth,
+}
+
+impl Display for FormulaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FormulaError::Syntax => "#ERROR!",
+            FormulaError::Name => "#NAME?",
+            FormulaError::Ref => "#REF!",
+            FormulaError::Value => "#VALUE!",
+            FormulaError::DivZero => "#DIV/0!",
+            FormulaError::Cycle => "#CYCLE!",
+            FormulaError::Depth => "#DEPTH!",
+        })
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Empty => Ok(()),
+            Value::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Text(text) => f.write_str(text),
+            Value::Bool(true) => f.write_str("TRUE"),
+            Value::Bool(false) => f.write_str("FALSE"),
+            Value::Error(e) => e.fmt(f),
+        }
+    }
+}
+
+pub fn is_formula(content: &str) -> bool {
+    content.starts_with('=')
+}
+
+impl TableContent {
+    pub fn has_formulas(&self) -> bool {
+        self.cells.iter().any(|c| c.content.as_deref().map_or(false, is_formula))
+    }
+
+    /// The value of every formula cell
+    pub fn evaluate_formulas(&self) -> HashMap<CellRef, Value> {
+        let mut evaluator = Evaluator { table: self, values: HashMap::new(), visiting: HashSet::new(), missing: Vec::new() };
+        if self.columns.is_empty() {
+            return HashMap::new();
+        }
+        for row in 0..self.height() {
+            for column in &self.columns {
+                let at = Pos { column: column.id, row };
+                if self.cell(at.column, at.row).and_then(|c| c.content.as_deref()).map_or(false, is_formula) {
+                    evaluator.cell_value(at);
+                }
+            }
+        }
+        evaluator.values.into_iter()
+            .map(|(at, value)| (CellRef { column: at.column, row: self.rows[at.row] }, value))
+            .collect()
+    }
+}
+
+/// Where a cell is while evaluating, rows being referred to by number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Pos {
+    column: ColumnID,
+    row: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Ident(String),
+    /// A column, by letters or `[name]`, and maybe a row number
+    Ref(ColumnSpec, Option<usize>),
+    Op(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ColumnSpec {
+    Letters(String),
+    Name(String),
+}
+
+#[derive(Debug, Clone)]
+struct RefSpec {
+    column: ColumnSpec,
+    /// Starting from 1
+    row: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Value(Value),
+    Ref(RefSpec),
+    Range(RefSpec, RefSpec),
+    Negate(Box<Expr>),
+    /// The first operand, then each operator with the operand after it, applied from the left
+    Binary(Box<Expr>, Vec<(&'static str, Expr)>),
+    Call(String, Vec<Expr>),
+}
+
+/// How high expressions can be, and how deeply the parser can go, as both recurse.
+/// Operators of the same precedence make one expression however many there are.
+const MAX_DEPTH: usize = 100;
+
+const OPERATORS: [&str; 17] = ["<=", ">=", "<>", "+", "-", "*", "/", "^", "&", "=", "<", ">", "(", ")", ",", ":", ";"];
+
+fn tokenize(formula: &str) -> Result<Vec<Token>, FormulaError> {
+    let chars = formula.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' && chars.get(i + 1).map_or(false, |c| c.is_ascii_digit()) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let number = chars[start..i].iter().collect::<String>();
+            tokens.push(Token::Number(number.parse().map_err(|_| FormulaError::Syntax)?));
+        } else if c == '"' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    Some('"') if chars.get(i + 1) == Some(&'"') => {
+                        text.push('"');
+                        i += 2;
+                    },
+                    Some('"') => break,
+                    Some(c) => {
+                        text.push(*c);
+                        i += 1;
+                    },
+                    None => return Err(FormulaError::Syntax),
+                }
+            }
+            i += 1;
+            tokens.push(Token::Text(text));
+        } else if c == '[' {
+            let end = chars[i..].iter().position(|c| *c == ']').ok_or(FormulaError::Syntax)? + i;
+            let name = chars[i + 1..end].iter().collect::<String>();
+            i = end + 1;
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let row = chars[start..i].iter().collect::<String>().parse().ok();
+            tokens.push(Token::Ref(ColumnSpec::Name(name.trim().to_string()), row));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                i += 1;
+            }
+            let ident = chars[start..i].iter().collect::<String>();
+            let letters = ident.trim_end_matches(|c: char| c.is_ascii_digit());
+            let is_cell = letters.len() < ident.len()
+                && !letters.is_empty()
+                && letters.chars().all(|c| c.is_ascii_alphabetic());
+            if is_cell {
+                let row = ident[letters.len()..].parse().map_err(|_| FormulaError::Syntax)?;
+                tokens.push(Token::Ref(ColumnSpec::Letters(letters.to_uppercase()), Some(row)));
+            } else {
+                tokens.push(Token::Ident(ident.to_uppercase()));
+            }
+        } else {
+            let rest = chars[i..].iter().take(2).collect::<String>();
+            let op = OPERATORS.iter().find(|op| rest.starts_with(*op)).ok_or(FormulaError::Syntax)?;
+            i += op.chars().count();
+            tokens.push(Token::Op(op));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    i: usize,
+    /// How many expressions the one being parsed is in
+    depth: usize,
+}
+
+/// An expression with its height
+type Parsed = Result<(Expr, usize), FormulaError>;
+
+fn parse(formula: &str) -> Result<Expr, FormulaError> {
+    let mut parser = Parser { tokens: tokenize(formula)?, i: 0, depth: 0 };
+    let (expr, _) = parser.comparison()?;
+    if parser.i < parser.tokens.len() {
+        return Err(FormulaError::Syntax);
+    }
+    Ok(expr)
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.i)
+    }
+
+    /// Takes the next token if it's one of `ops`
+    fn op(&mut self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => {
+                let op = *op;
+                self.i += 1;
+                Some(op)
+            },
+            _ => None,
+        }
+    }
+
+    /// Parses with `parse` inside the current expression, as long as that's not too deep
+    fn nested(&mut self, parse: fn(&mut Self) -> Parsed) -> Parsed {
+        if self.depth >= MAX_DEPTH {
+            return Err(FormulaError::Depth);
+        }
+        self.depth += 1;
+        let parsed = parse(self);
+        self.depth -= 1;
+        parsed
+    }
+
+    fn binary(&mut self, ops: &[&'static str], next: fn(&mut Self) -> Parsed) -> Parsed {
+        let (first, mut height) = next(self)?;
+        let mut rest = Vec::new();
+        while let Some(op) = self.op(ops) {
+            let (right, right_height) = next(self)?;
+            height = height.max(right_height);
+            rest.push((op, right));
+        }
+        if rest.is_empty() {
+            return Ok((first, height));
+        }
+        Ok((Expr::Binary(Box::new(first), rest), above(height)?))
+    }
+
+    fn comparison(&mut self) -> Parsed {
+        self.binary(&["=", "<>", "<", ">", "<=", ">="], Self::concat)
+    }
+
+    fn concat(&mut self) -> Parsed {
+        self.binary(&["&"], Self::additive)
+    }
+
+    fn additive(&mut self) -> Parsed {
+        self.binary(&["+", "-"], Self::term)
+    }
+
+    fn term(&mut self) -> Parsed {
+        self.binary(&["*", "/"], Self::power)
+    }
+
+    fn power(&mut self) -> Parsed {
+        self.binary(&["^"], Self::unary)
+    }
+
+    fn unary(&mut self) -> Parsed {
+        match self.op(&["-", "+"]) {
+            Some("-") => {
+                let (expr, height) = self.nested(Self::unary)?;
+                Ok((Expr::Negate(Box::new(expr)), above(height)?))
+            },
+            Some(_) => self.nested(Self::unary),
+            None => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Parsed {
+        let token = self.peek().cloned().ok_or(FormulaError::Syntax)?;
+        self.i += 1;
+        match token {
+            Token::Number(n) => Ok((Expr::Value(Value::Number(n)), 1)),
+            Token::Text(text) => Ok((Expr::Value(Value::Text(text)), 1)),
+            Token::Ref(column, row) => self.reference(RefSpec { column, row }),
+            Token::Ident(name) => {
+                if self.op(&["("]).is_some() {
+                    let mut args = Vec::new();
+                    let mut height = 0;
+                    if self.op(&[")"]).is_none() {
+                        loop {
+                            let (arg, arg_height) = self.nested(Self::comparison)?;
+                            args.push(arg);
+                            height = height.max(arg_height);
+                            match self.op(&[",", ";", ")"]) {
+                                Some(")") => break,
+                                Some(_) => (),
+                                None => return Err(FormulaError::Syntax),
+                            }
+                        }
+                    }
+                    Ok((Expr::Call(name, args), above(height)?))
+                } else if matches!(self.peek(), Some(Token::Op(":"))) && name.chars().all(|c| c.is_ascii_alphabetic()) {
+                    self.reference(RefSpec { column: ColumnSpec::Letters(name), row: None })
+                } else {
+                    let value = match name.as_str() {
+                        "TRUE" => Value::Bool(true),
+                        "FALSE" => Value::Bool(false),
+                        _ => Value::Error(FormulaError::Name),
+                    };
+                    Ok((Expr::Value(value), 1))
+                }
+            },
+            Token::Op("(") => {
+                let parsed = self.nested(Self::comparison)?;
+                self.op(&[")"]).ok_or(FormulaError::Syntax)?;
+                Ok(parsed)
+            },
+            Token::Op(_) => Err(FormulaError::Syntax),
+        }
+    }
+
+    /// A reference starting with `start`, or a range if a `:` follows
+    fn reference(&mut self, start: RefSpec) -> Parsed {
+        if self.op(&[":"]).is_none() {
+            return Ok((Expr::Ref(start), 1));
+        }
+        let end = match self.peek().cloned() {
+            Some(Token::Ref(column, row)) => RefSpec { column, row },
+            Some(Token::Ident(name)) if name.chars().all(|c| c.is_ascii_alphabetic()) => {
+                RefSpec { column: ColumnSpec::Letters(name), row: None }
+            },
+            _ => return Err(FormulaError::Syntax),
+        };
+        self.i += 1;
+        Ok((Expr::Range(start, end), 1))
+    }
+}
+
+/// The height of an expression over ones at most `height` high
+fn above(height: usize) -> Result<usize, FormulaError> {
+    if height >= MAX_DEPTH {
+        Err(FormulaError::Depth)
+    } else {
+        Ok(height + 1)
+    }
+}
+
+/// Works out formulas without recursing from one to those it refers to, so long chains of them can't overflow the stack.
+/// A formula referring to another one not worked out yet is set aside until that one is, and then evaluated again.
+struct Evaluator<'a> {
+    table: &'a TableContent,
+    values: HashMap<Pos, Value>,
+    /// Formulas set aside, which referring to again is a cycle
+    visiting: HashSet<Pos>,
+    /// Formulas the one being evaluated refers to that aren't worked out yet, in the order it needs them
+    missing: Vec<Pos>,
+}
+
+impl<'a> Evaluator<'a> {
+    /// The value of the cell at `at`, working out the formulas it depends on first
+    fn cell_value(&mut self, at: Pos) -> Value {
+        let mut pending = vec![at];
+        let mut queued = HashSet::new();
+        while let Some(&next) = pending.last() {
+            let content = match self.table.cell(next.column, next.row).and_then(|c| c.content.as_deref()) {
+                Some(content) if is_formula(content) && !self.values.contains_key(&next) => content,
+                _ => {
+                    pending.pop();
+                    continue;
+                },
+            };
+            self.visiting.insert(next);
+            let value = match parse(&content[1..]) {
+                Ok(expr) => self.eval(&expr, next),
+                Err(e) => Value::Error(e),
+            };
+            if self.missing.is_empty() {
+                self.visiting.remove(&next);
+                self.values.insert(next, value);
+                pending.pop();
+            } else {
+                // Ranges overlap, so each cell is only queued once, unless nothing else is missing
+                let first = self.missing[0];
+                let mut missing = self.missing.drain(..).filter(|m| queued.insert(*m)).collect::<Vec<_>>();
+                if missing.is_empty() {
+                    missing.push(first);
+                }
+                pending.extend(missing.into_iter().rev());
+            }
+        }
+        self.known_value(at).unwrap_or(Value::Empty)
+    }
+
+    /// The value of the cell at `at` if it's not a formula or it's been worked out
+    fn known_value(&self, at: Pos) -> Option<Value> {
+        match self.table.cell(at.column, at.row).and_then(|c| c.content.as_deref()) {
+            Some(content) if is_formula(content) => self.values.get(&at).cloned(),
+            Some(content) => Some(literal(content)),
+            None => Some(Value::Empty),
+        }
+    }
+
+    /// The value of the cell at `at` for the formula being evaluated.
+    /// If it's a formula not worked out yet the value is a stand-in, and it's noted as missing if `note`.
+    fn referred_value(&mut self, at: Pos, note: bool) -> Value {
+        if let Some(value) = self.known_value(at) {
+            return value;
+        }
+        if self.visiting.contains(&at) {
+            return Value::Error(FormulaError::Cycle);
+        }
+        if note {
+            self.missing.push(at);
+        }
+        Value::Empty
+    }
+
+    fn column_index(&self, column: &ColumnSpec) -> Option<usize> {
+        match column {
+            ColumnSpec::Letters(letters) => {
+                let n = letters.chars().fold(0usize, |n, c| n.saturating_mul(26).saturating_add(c as usize - 'A' as usize + 1));
+                (n <= self.table.width()).then_some(n - 1)
+            },
+            ColumnSpec::Name(name) => {
+                let name = name.to_lowercase();
+                self.table.columns.iter().position(|c| c.name.trim().to_lowercase() == name)
+            },
+        }
+    }
+
+    fn resolve(&self, spec: &RefSpec, here: Pos) -> Result<Pos, FormulaError> {
+        let column = self.column_index(&spec.column).ok_or(FormulaError::Ref)?;
+        let row = match spec.row {
+            Some(0) => return Err(FormulaError::Ref),
+            Some(row) => row - 1,
+            None => here.row,
+        };
+        if row >= self.table.height() {
+            return Err(FormulaError::Ref);
+        }
+        Ok(Pos { column: self.table.columns[column].id, row })
+    }
+
+    fn range(&mut self, start: &RefSpec, end: &RefSpec) -> Result<Vec<Value>, FormulaError> {
+        let a = self.column_index(&start.column).ok_or(FormulaError::Ref)?;
+        let b = self.column_index(&end.column).ok_or(FormulaError::Ref)?;
+        let height = self.table.height();
+        let rows = match (start.row, end.row) {
+            (None, None) => 0..height,
+            (Some(s), Some(e)) if s != 0 && e != 0 => {
+                let (s, e) = (s.min(e) - 1, s.max(e));
+                if e > height {
+                    return Err(FormulaError::Ref);
+                }
+                s..e
+            },
+            _ => return Err(FormulaError::Ref),
+        };
+        let columns = self.table.columns[a.min(b)..=a.max(b)].iter().map(|c| c.id).collect::<Vec<_>>();
+        // Every cell of a range is needed, so all those missing are noted at once rather than one evaluation each
+        let note = self.missing.is_empty();
+        let mut values = Vec::new();
+        for row in rows {
+            for column in &columns {
+                values.push(self.referred_value(Pos { column: *column, row }, note));
+            }
+        }
+        Ok(values)
+    }
+
+    fn eval(&mut self, expr: &Expr, here: Pos) -> Value {
+        match expr {
+            Expr::Value(value) => value.clone(),
+            Expr::Ref(spec) => match self.resolve(spec, here) {
+                // Only the first missing, as what the formula refers to after it may depend on its value
+                Ok(at) => {
+                    let note = self.missing.is_empty();
+                    self.referred_value(at, note)
+                },
+                Err(e) => Value::Error(e),
+            },
+            Expr::Range(_, _) => Value::Error(FormulaError::Value),
+            Expr::Negate(expr) => match number(&self.eval(expr, here)) {
+                Ok(n) => Value::Number(-n),
+                Err(e) => Value::Error(e),
+            },
+            Expr::Binary(first, rest) => {
+                let mut value = self.eval(first, here);
+                for (op, b) in rest {
+                    let b = self.eval(b, here);
+                    value = binary(op, value, b);
+                }
+                value
+            },
+            Expr::Call(name, args) => self.call(name, args, here).unwrap_or_else(Value::Error),
+        }
+    }
+
+    /// Evaluates the arguments, with ranges spread into their values
+    fn spread(&mut self, args: &[Expr], here: Pos) -> Result<Vec<Value>, FormulaError> {
+        let mut values = Vec::new();
+        for arg in args {
+            match arg {
+                Expr::Range(start, end) => values.extend(self.range(start, end)?),
+                arg => values.push(self.eval(arg, here)),
+            }
+        }
+        match values.iter().find_map(|v| if let Value::Error(e) = v { Some(*e) } else { None }) {
+            Some(e) => Err(e),
+            None => Ok(values),
+        }
+    }
+
+    fn call(&mut self, name: &str, args: &[Expr], here: Pos) -> Result<Value, FormulaError> {
+        let numbers = |values: Vec<Value>| values.into_iter().filter_map(|v| match v {
+            Value::Number(n) => Some(n),
+            Value::Text(t) => t.trim().parse::<f64>().ok(),
+            _ => None,
+        }).collect::<Vec<_>>();
+        Ok(match name {
+            "SUM" => Value::Number(numbers(self.spread(args, here)?).iter().sum()),
+            "AVG" | "AVERAGE" => {
+                let numbers = numbers(self.spread(args, here)?);
+                if numbers.is_empty() {
+                    return Err(FormulaError::DivZero);
+                }
+                Value::Number(numbers.iter().sum::<f64>() / numbers.len() as f64)
+            },
+            "COUNT" => Value::Number(numbers(self.spread(args, here)?).len() as f64),
+            "COUNTA" => Value::Number(self.spread(args, here)?.iter().filter(|v| !text(v).is_empty()).count() as f64),
+            "MIN" => Value::Number(numbers(self.spread(args, here)?).into_iter().reduce(f64::min).unwrap_or(0.0)),
+            "MAX" => Value::Number(numbers(self.spread(args, here)?).into_iter().reduce(f64::max).unwrap_or(0.0)),
+            "CONCAT" => Value::Text(self.spread(args, here)?.iter().map(text).collect()),
+            "AND" => Value::Bool(self.spread(args, here)?.iter().map(truthy).collect::<Result<Vec<_>, _>>()?.iter().all(|b| *b)),
+            "OR" => Value::Bool(self.spread(args, here)?.iter().map(truthy).collect::<Result<Vec<_>, _>>()?.iter().any(|b| *b)),
+            "IF" => {
+                if args.len() < 2 || args.len() > 3 {
+                    return Err(FormulaError::Value);
+                }
+                if truthy(&self.eval(&args[0], here))? {
+                    self.eval(&args[1], here)
+                } else if let Some(otherwise) = args.get(2) {
+                    self.eval(otherwise, here)
+                } else {
+                    Value::Bool(false)
+                }
+            },
+            _ => {
+                let values = args.iter().map(|arg| self.eval(arg, here)).collect::<Vec<_>>();
+                if let Some(Value::Error(e)) = values.iter().find(|v| matches!(v, Value::Error(_))) {
+                    return Err(*e);
+                }
+                let count = |n: usize| (values.len() == n).then_some(()).ok_or(FormulaError::Value);
+                let optional = |i: usize, default: f64| values.get(i).map_or(Ok(default), number);
+                match name {
+                    "NOT" => { count(1)?; Value::Bool(!truthy(&values[0])?) },
+                    "ABS" => { count(1)?; Value::Number(number(&values[0])?.abs()) },
+                    "ROUND" => {
+                        let places = optional(1, 0.0)?;
+                        let factor = 10f64.powi(places as i32);
+                        Value::Number((number(values.first().ok_or(FormulaError::Value)?)? * factor).round() / factor)
+                    },
+                    "LEN" => { count(1)?; Value::Number(text(&values[0]).chars().count() as f64) },
+                    "UPPER" => { count(1)?; Value::Text(text(&values[0]).to_uppercase()) },
+                    "LOWER" => { count(1)?; Value::Text(text(&values[0]).to_lowercase()) },
+                    "TRIM" => { count(1)?; Value::Text(text(&values[0]).split_whitespace().collect::<Vec<_>>().join(" ")) },
+                    "LEFT" | "RIGHT" => {
+                        let t = text(values.first().ok_or(FormulaError::Value)?);
+                        let n = optional(1, 1.0)?.max(0.0) as usize;
+                        let chars = t.chars();
+                        Value::Text(if name == "LEFT" {
+                            chars.take(n).collect()
+                        } else {
+                            let skip = t.chars().count().saturating_sub(n);
+                            chars.skip(skip).collect()
+                        })
+                    },
+                    _ => return Err(FormulaError::Name),
+                }
+            },
+        })
+    }
+}
+
+/// The value of a cell that isn't a formula
+fn literal(content: &str) -> Value {
+    let trimmed = content.trim();
+    if trimmed.is_empty() {
+        Value::Empty
+    } else if let Ok(n) = trimmed.parse::<f64>() {
+        Value::Number(n)
+    } else if trimmed.eq_ignore_ascii_case("true") {
+        Value::Bool(true)
+    } else if trimmed.eq_ignore_ascii_case("false") {
+        Value::Bool(false)
+    } else {
+        Value::Text(content.to_string())
+    }
+}
+
+fn number(value: &Value) -> Result<f64, FormulaError> {
+    match value {
+        Value::Empty => Ok(0.0),
+        Value::Number(n) => Ok(*n),
+        Value::Bool(b) => Ok(if *b { 1.0 } else { 0.0 }),
+        Value::Text(t) => t.trim().parse().map_err(|_| FormulaError::Value),
+        Value::Error(e) => Err(*e),
+    }
+}
+
+fn text(value: &Value) -> String {
+    value.to_string()
+}
+
+fn truthy(value: &Value) -> Result<bool, FormulaError> {
+    match value {
+        Value::Bool(b) => Ok(*b),
+        Value::Text(t) if t.eq_ignore_ascii_case("true") => Ok(true),
+        Value::Text(t) if t.eq_ignore_ascii_case("false") => Ok(false),
+        value => number(value).map(|n| n != 0.0),
+    }
+}
+
+fn binary(op: &str, a: Value, b: Value) -> Value {
+    if let Value::Error(e) = a {
+        return Value::Error(e);
+    }
+    if let Value::Error(e) = b {
+        return Value::Error(e);
+    }
+    let arithmetic = |f: fn(f64, f64) -> Value| match (number(&a), number(&b)) {
+        (Ok(a), Ok(b)) => f(a, b),
+        (Err(e), _) | (_, Err(e)) => Value::Error(e),
+    };
+    match op {
+        "+" => arithmetic(|a, b| Value::Number(a + b)),
+        "-" => arithmetic(|a, b| Value::Number(a - b)),
+        "*" => arithmetic(|a, b| Value::Number(a * b)),
+        "/" => arithmetic(|a, b| if b == 0.0 { Value::Error(FormulaError::DivZero) } else { Value::Number(a / b) }),
+        "^" => arithmetic(|a, b| {
+            let n = a.powf(b);
+            if n.is_finite() { Value::Number(n) } else { Value::Error(FormulaError::Value) }
+        }),
+        "&" => Value::Text(text(&a) + &text(&b)),
+        _ => {
+            let ordering = match (&a, &b) {
+                (Value::Number(_) | Value::Empty | Value::Bool(_), Value::Number(_) | Value::Empty | Value::Bool(_)) => {
+                    number(&a).unwrap().partial_cmp(&number(&b).unwrap())
+                },
+                _ => Some(text(&a).to_lowercase().cmp(&text(&b).to_lowercase())),
+            };
+            let ordering = match ordering {
+                Some(ordering) => ordering,
+                None => return Value::Error(FormulaError::Value),
+            };
+            Value::Bool(match op {
+                "=" => ordering.is_eq(),
+                "<>" => ordering.is_ne(),
+                "<" => ordering.is_lt(),
+                ">" => ordering.is_gt(),
+                "<=" => ordering.is_le(),
+                _ => ordering.is_ge(),
+            })
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The values of the first column's cells
+    fn evaluate(cells: &[String]) -> Vec<Value> {
+        let table = TableContent::from_csv(&format!("A\n{}\n", cells.join("\n")), ',');
+        let values = table.evaluate_formulas();
+        table.rows.iter()
+            .map(|&row| values.get(&CellRef { column: table.columns[0].id, row }).cloned().unwrap_or(Value::Empty))
+            .collect()
+    }
+
+    #[test]
+    fn cycles() {
+        let values = evaluate(&["=A2".to_string(), "=A1+1".to_string(), "=A3".to_string(), "=SUM(A1:A4)".to_string()]);
+        assert!(values.iter().all(|v| *v == Value::Error(FormulaError::Cycle)));
+    }
+
+    #[test]
+    fn unused_branches_are_not_cycles() {
+        let values = evaluate(&["=IF(A2; 1; A1)".to_string(), "=TRUE".to_string()]);
+        assert_eq!(values, vec![Value::Number(1.0), Value::Bool(true)]);
+    }
+
+    #[test]
+    fn deep_chains() {
+        // Each row refers to the one after it, so the first one needs all the others
+        let n = 10_000;
+        let cells = (1..n).map(|row| format!("=A{}+1", row + 1)).chain(["=1".to_string()]).collect::<Vec<_>>();
+        let values = evaluate(&cells);
+        assert_eq!(values[0], Value::Number(n as f64));
+        // Each row adds up all the ones after it, which makes the work grow with the square of the rows
+        let n = 1_000;
+        let cells = (1..=n).map(|row| format!("=SUM(A{}:A{})", row + 1, n + 1)).chain(["=1".to_string()]).collect::<Vec<_>>();
+        assert_eq!(evaluate(&cells)[0], Value::Number(2f64.powi(n - 1)));
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let n = 50_000;
+        let depth = Value::Error(FormulaError::Depth);
+        let nested = format!("={}1{}", "(".repeat(n), ")".repeat(n));
+        let negated = format!("={}1", "-".repeat(n));
+        let called = format!("={}1{}", "ABS(".repeat(n), ")".repeat(n));
+        assert_eq!(evaluate(&[nested, negated, called]), vec![depth.clone(), depth.clone(), depth]);
+        let shallow = format!("={}1{}", "(".repeat(MAX_DEPTH - 1), ")".repeat(MAX_DEPTH - 1));
+        assert_eq!(evaluate(&[shallow]), vec![Value::Number(1.0)]);
+    }
+
+    #[test]
+    fn long_chains() {
+        let n = 50_000;
+        let added = format!("=1{}", "+1".repeat(n));
+        let mixed = format!("=1{}", "*2/2-1+1".repeat(n));
+        let referred = format!("={}", (4..n + 4).map(|row| format!("A{row}")).collect::<Vec<_>>().join("+"));
+        let cells = [added, mixed, referred].into_iter().chain((0..n).map(|_| "1".to_string())).collect::<Vec<_>>();
+        assert_eq!(evaluate(&cells)[..3], [Value::Number(n as f64 + 1.0), Value::Number(1.0), Value::Number(n as f64)]);
+        // Left to right, as before
+        assert_eq!(evaluate(&["=2^3^2".to_string(), "=8-2-1".to_string()]), vec![Value::Number(64.0), Value::Number(5.0)]);
+    }
+}
@@ -1,12 +1,79 @@
+use std::collections::HashSet;
+
+use self::chart::Chart;
 use self::column::{Column, ColumnID};
+use self::pivot::Pivot;
+use self::row::RowID;
+use self::view::{Filter, SortKey};
 
+pub mod aggregate;
+pub mod chart;
+pub mod clipboard;
 pub mod column;
+pub mod csv;
+pub mod formula;
+pub mod pivot;
+pub mod relation;
 pub mod row;
+pub mod view;
 
 #[derive(Debug, Default)]
 pub struct TableContent {
     pub columns: Vec<Column>,
     pub cells: Vec<TableCell>,
+    /// The ID of each row, in the order of `cells`
+    pub rows: Vec<RowID>,
+    /// How the rows are shown, the first key sorting first
+    pub sort: Vec<SortKey>,
+    /// Rules the shown rows all pass
+    pub filters: Vec<Filter>,
+    pub layout: Layout,
+    /// The single select column the board groups rows by
+    pub board_column: Option<ColumnID>,
+    /// The date column the calendar places rows by
+    pub calendar_column: Option<ColumnID>,
+    /// Charts shown under the grid
+    pub charts: Vec<Chart>,
+    /// The summary shown in the pivot layout
+    pub pivot: Option<Pivot>,
+}
+
+/// The ways a table can be shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Layout {
+    #[default]
+    Grid,
+    /// Rows as cards in lanes, by the value of a single select column
+    Board,
+    /// Rows on the days of a month, by the value of a date column
+    Calendar,
+    /// One row at a time, with an editor for each cell
+    Form,
+    /// A summary of the rows, grouped by the values of a column
+    Pivot,
+}
+
+impl Layout {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Layout::Grid => "grid",
+            Layout::Board => "board",
+            Layout::Calendar => "calendar",
+            Layout::Form => "form",
+            Layout::Pivot => "pivot",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "grid" => Layout::Grid,
+            "board" => Layout::Board,
+            "calendar" => Layout::Calendar,
+            "form" => Layout::Form,
+            "pivot" => Layout::Pivot,
+            _ => return None,
+        })
+    }
 }
 
 impl TableContent {
@@ -38,22 +105,99 @@ impl TableContent {
     pub fn is_empty(&self) -> bool {
         self.cells.is_empty()
     }
+    /// Adds `column` at `at`, with a missing cell in every row
+    pub fn insert_column(&mut self, at: usize, column: Column) {
+        let width = self.columns.len();
+        if let Some(height) = self.cells.len().checked_div(width) {
+            for row in (0..height).rev() {
+                self.cells.insert(row * width + at, TableCell { content: None });
+            }
+        }
+        self.columns.insert(at, column);
+    }
+    pub fn remove_column(&mut self, at: usize) {
+        let width = self.columns.len();
+        for row in (0..self.cells.len() / width).rev() {
+            self.cells.remove(row * width + at);
+        }
+        self.columns.remove(at);
+    }
+    pub fn move_column(&mut self, from: usize, to: usize) {
+        fn shift<T>(items: &mut [T], from: usize, to: usize) {
+            if from < to {
+                items[from..=to].rotate_left(1);
+            } else {
+                items[to..=from].rotate_right(1);
+            }
+        }
+        let width = self.columns.len();
+        for row in self.cells.chunks_mut(width) {
+            shift(row, from, to);
+        }
+        shift(&mut self.columns, from, to);
+    }
+    /// Values found in more than one cell of the column at `column`
+    pub fn duplicates(&self, column: usize) -> HashSet<String> {
+        let width = self.columns.len();
+        duplicate_values(self.cells.chunks(width).filter_map(|row| row[column].content.as_deref()))
+    }
+    /// Names of the unique columns holding duplicates, with how many repeated values each has
+    pub fn unique_violations(&self) -> Vec<(String, usize)> {
+        self.columns.iter().enumerate()
+            .filter(|(_, column)| column.unique)
+            .map(|(i, column)| (column.name.clone(), self.duplicates(i).len()))
+            .filter(|(_, n)| *n != 0)
+            .collect()
+    }
+    /// Removes the rows repeating a value of the column at `column`, keeping the first of each,
+    /// returns how many were removed
+    pub fn dedup_rows(&mut self, column: usize) -> usize {
+        self.fill_row_ids();
+        let width = self.columns.len();
+        let mut seen = HashSet::new();
+        let mut cells = Vec::with_capacity(self.cells.len());
+        let mut rows = Vec::with_capacity(self.rows.len());
+        let mut removed = 0;
+        for (row, id) in self.cells.chunks(width).zip(&self.rows) {
+            match &row[column].content {
+                Some(value) if !value.is_empty() && !seen.insert(value.clone()) => removed += 1,
+                _ => {
+                    cells.extend_from_slice(row);
+                    rows.push(*id);
+                },
+            }
+        }
+        self.cells = cells;
+        self.rows = rows;
+        removed
+    }
     pub fn insert_row(&mut self, before: usize) {
+        self.fill_row_ids();
         let columns = self.columns.len();
         let i = before * columns;
         for _ in 0..columns {
             self.cells.insert(i, TableCell { content: None });
         }
+        self.rows.insert(before, RowID::random());
     }
 }
 
+/// Values appearing more than once, empty ones don't count
+pub fn duplicate_values<'a>(values: impl IntoIterator<Item = &'a str>) -> HashSet<String> {
+    let mut seen = HashSet::new();
+    values.into_iter()
+        .filter(|v| !v.is_empty() && !seen.insert(*v))
+        .map(|v| v.to_string())
+        .collect()
+}
+
 #[derive(Debug, Clone)]
 pub struct TableCell {
     pub content: Option<String>
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct CellRef {
     pub column: ColumnID,
-    pub row: usize,
+    pub row: RowID,
 }
\ No newline at end of file
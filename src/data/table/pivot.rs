@@ -0,0 +1,113 @@
+//! Summaries of a table grouping its rows by the values of one column
+
+use std::collections::HashMap;
+
+use super::{CellRef, TableCell, TableContent, aggregate::Aggregate, column::{Column, ColumnID, ColumnKind}, formula::is_formula, view::compare_values};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pivot {
+    /// The column whose values make the groups
+    pub group: ColumnID,
+    /// The column aggregated in each group
+    pub value: ColumnID,
+    pub aggregate: Aggregate,
+}
+
+impl TableContent {
+    /// A table with a row for each value of the grouping column, and the aggregate of the rows having it.
+    /// The rows of a multi select cell count in the group of each of its options, and empty cells make a group too.
+    pub fn pivot(&self, pivot: &Pivot) -> Option<TableContent> {
+        let group = self.columns.iter().position(|c| c.id == pivot.group)?;
+        let value = self.columns.iter().position(|c| c.id == pivot.value)?;
+        let values = if self.has_formulas() { self.evaluate_formulas() } else { HashMap::new() };
+        let shown = |cells: &[TableCell], i: usize, row| {
+            let content = cells[i].content.as_deref()?;
+            if is_formula(content) {
+                values.get(&CellRef { column: self.columns[i].id, row }).map(|v| v.to_string())
+            } else {
+                Some(content.to_string())
+            }
+        };
+
+        let mut groups: Vec<(Option<String>, Vec<Option<String>>)> = Vec::new();
+        let mut positions = HashMap::new();
+        for (&row, cells) in self.rows.iter().zip(self.cells.chunks(self.width())) {
+            let key = shown(cells, group, row).map(|k| k.trim().to_string()).filter(|k| !k.is_empty());
+            let keys = match (&self.columns[group].kind, key) {
+                (ColumnKind::MultiSelect(_), Some(key)) => key.lines().map(|k| Some(k.to_string())).collect(),
+                (_, key) => vec![key],
+            };
+            let value = shown(cells, value, row);
+            for key in keys {
+                let n = *positions.entry(key.clone()).or_insert_with(|| {
+                    groups.push((key, Vec::new()));
+                    groups.len() - 1
+                });
+                groups[n].1.push(value.clone());
+            }
+        }
+
+        let group_kind = match &self.columns[group].kind {
+            ColumnKind::MultiSelect(options) => ColumnKind::Select(options.clone()),
+            kind => kind.clone(),
+        };
+        groups.sort_by(|a, b| compare_values(&group_kind, a.0.as_deref(), b.0.as_deref()));
+        let value_kind = &self.columns[value].kind;
+        let mut group_column = Column::new(self.columns[group].name.clone());
+        group_column.kind = group_kind;
+        let mut value_column = Column::new(format!("{} ({})", self.columns[value].name, pivot.aggregate.name()));
+        value_column.kind = match pivot.aggregate {
+            Aggregate::Min | Aggregate::Max => value_kind.clone(),
+            _ => ColumnKind::Number,
+        };
+
+        let mut summary = TableContent { columns: vec![group_column, value_column], ..Default::default() };
+        for (key, values) in groups {
+            let aggregate = pivot.aggregate.compute(value_kind, values.iter().map(Option::as_deref));
+            summary.cells.push(TableCell { content: key });
+            summary.cells.push(TableCell { content: aggregate });
+        }
+        summary.fill_row_ids();
+        Some(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> TableContent {
+        let mut table = TableContent::from_csv("team,n,tags\nb,1,x\na,2,\"x\ny\"\nb,=B1*10,\n,4,y\n", ',');
+        table.columns[1].kind = ColumnKind::Number;
+        table.columns[2].kind = ColumnKind::MultiSelect(vec!["x".to_string(), "y".to_string()]);
+        table
+    }
+
+    fn summarize(table: &TableContent, group: usize, value: usize, aggregate: Aggregate) -> Vec<Option<String>> {
+        let pivot = Pivot { group: table.columns[group].id, value: table.columns[value].id, aggregate };
+        table.pivot(&pivot).unwrap().cells.into_iter().map(|c| c.content).collect()
+    }
+
+    fn cells(cells: &[Option<&str>]) -> Vec<Option<String>> {
+        cells.iter().map(|c| c.map(str::to_string)).collect()
+    }
+
+    #[test]
+    fn formula_values() {
+        assert_eq!(summarize(&table(), 0, 1, Aggregate::Sum), cells(&[Some("a"), Some("2"), Some("b"), Some("11"), None, Some("4")]));
+    }
+
+    #[test]
+    fn multi_select_fan_out() {
+        assert_eq!(summarize(&table(), 2, 1, Aggregate::Count), cells(&[Some("x"), Some("2"), Some("y"), Some("2"), None, Some("1")]));
+    }
+
+    #[test]
+    fn empty_value_group() {
+        let mut table = table();
+        // Whitespace only counts as empty, so the row of "a" joins the one with no team
+        table.cells[3].content = Some("  ".to_string());
+        let summary = summarize(&table, 0, 1, Aggregate::Count);
+        assert_eq!(summary, cells(&[Some("b"), Some("2"), None, Some("2")]));
+    }
+}
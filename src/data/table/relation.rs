@@ -0,0 +1,52 @@
+//! Links from rows of one table to rows of another, made by relation columns
+
+use std::collections::HashMap;
+
+use crate::data::FileID;
+
+use super::{CellRef, TableContent, column::ColumnKind, formula::is_formula, row::RowID};
+
+impl TableContent {
+    /// Each row with the value of its first column, which is what links to the row show
+    pub fn row_labels(&self) -> Vec<(RowID, String)> {
+        if self.columns.is_empty() {
+            return vec![];
+        }
+        let values = if self.has_formulas() { self.evaluate_formulas() } else { HashMap::new() };
+        let column = self.columns[0].id;
+        self.rows.iter().zip(self.cells.chunks(self.width())).map(|(&row, cells)| {
+            let content = cells[0].content.as_deref().unwrap_or("");
+            let label = if is_formula(content) {
+                values.get(&CellRef { column, row }).map(|v| v.to_string()).unwrap_or_default()
+            } else {
+                content.trim().to_string()
+            };
+            (row, label)
+        }).collect()
+    }
+
+    /// The rows of this table linking to each row of the `target` table, through its relation columns
+    pub fn links_to(&self, target: FileID) -> HashMap<RowID, Vec<RowID>> {
+        let mut links: HashMap<RowID, Vec<RowID>> = HashMap::new();
+        if self.columns.is_empty() {
+            return links;
+        }
+        let columns = self.columns.iter().enumerate()
+            .filter(|(_, c)| c.kind == ColumnKind::Relation(target))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        for (&row, cells) in self.rows.iter().zip(self.cells.chunks(self.width())) {
+            for &i in &columns {
+                for linked in linked_rows(cells[i].content.as_deref().unwrap_or("")) {
+                    links.entry(linked).or_default().push(row);
+                }
+            }
+        }
+        links
+    }
+}
+
+/// The rows a relation cell links to
+pub fn linked_rows(content: &str) -> Vec<RowID> {
+    content.lines().filter_map(|line| line.trim().parse().ok()).collect()
+}
@@ -1,14 +1,55 @@
-use std::mem::swap;
+use std::mem::{swap, take};
+use std::str::FromStr;
+use std::vec::IntoIter;
+
+use base64::{engine::general_purpose, DecodeError, Engine};
+use rand::random;
 
 use super::{TableContent, TableCell, column::{Column, ColumnID}};
 
+/// Identifies a row wherever it's moved to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RowID(u64);
+
+impl RowID {
+    pub fn random() -> Self {
+        Self(random::<u64>())
+    }
+}
+
+impl ToString for RowID {
+    fn to_string(&self) -> String {
+        general_purpose::URL_SAFE_NO_PAD.encode(self.0.to_be_bytes())
+    }
+}
+
+impl FromStr for RowID {
+    type Err = DecodeError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        general_purpose::URL_SAFE_NO_PAD.decode(s)
+            .and_then(|x| x.try_into().map_err(|_| DecodeError::InvalidLength))
+            .map(|x| Self(u64::from_be_bytes(x)))
+    }
+}
+
 pub struct RowIterator<'a> {
     table: &'a mut TableContent,
+    ids: IntoIter<RowID>,
 }
 
 impl TableContent {
     pub fn take_rows(&mut self) -> RowIterator {
-        RowIterator { table: self }
+        let ids = take(&mut self.rows).into_iter();
+        RowIterator { table: self, ids }
+    }
+
+    /// Gives new IDs to the rows missing one, like the ones of files from before rows had them
+    pub fn fill_row_ids(&mut self) {
+        let height = if self.columns.is_empty() { 0 } else { self.height() };
+        self.rows.truncate(height);
+        while self.rows.len() < height {
+            self.rows.push(RowID::random());
+        }
     }
 }
 
@@ -23,6 +64,7 @@ impl<'a> Iterator for RowIterator<'a> {
         let mut tail = self.table.cells.split_off(columns);
         swap(&mut self.table.cells, &mut tail);
         let a = Row {
+            id: self.ids.next().unwrap_or_else(RowID::random),
             cells: tail,
         };
         Some(a)
@@ -31,16 +73,26 @@ impl<'a> Iterator for RowIterator<'a> {
 
 #[derive(Debug, Clone)]
 pub struct Row {
+    pub id: RowID,
     pub cells: Vec<TableCell>,
 }
 
 impl Row {
     pub fn create_empty(l: usize) -> Self {
         Self {
+            id: RowID::random(),
             cells: vec![TableCell { content: None }; l]
         }
     }
 
+    /// The same cells, as a new row
+    pub fn duplicate(&self) -> Self {
+        Self {
+            id: RowID::random(),
+            cells: self.cells.clone(),
+        }
+    }
+
     pub fn get_cell(&self, columns: &Vec<Column>, id: ColumnID) -> Option<&TableCell> {
         for (i, column) in columns.iter().enumerate() {
             if column.id == id {
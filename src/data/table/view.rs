@@ -0,0 +1,133 @@
+//! Sorting and filtering rows for display, without changing the order they're stored in
+
+use std::cmp::Ordering;
+
+use super::column::{ColumnID, ColumnKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortKey {
+    pub column: ColumnID,
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    pub column: ColumnID,
+    pub rule: FilterRule,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterRule {
+    Contains(String),
+    DoesNotContain(String),
+    Is(String),
+    IsNot(String),
+    IsEmpty,
+    IsNotEmpty,
+    GreaterThan(String),
+    LessThan(String),
+}
+
+impl FilterRule {
+    pub const NAMES: [&'static str; 8] = ["contains", "does-not-contain", "is", "is-not", "is-empty", "is-not-empty", "greater-than", "less-than"];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FilterRule::Contains(_) => "contains",
+            FilterRule::DoesNotContain(_) => "does-not-contain",
+            FilterRule::Is(_) => "is",
+            FilterRule::IsNot(_) => "is-not",
+            FilterRule::IsEmpty => "is-empty",
+            FilterRule::IsNotEmpty => "is-not-empty",
+            FilterRule::GreaterThan(_) => "greater-than",
+            FilterRule::LessThan(_) => "less-than",
+        }
+    }
+
+    /// The rule called `name`, comparing with `value` if it takes one
+    pub fn from_name(name: &str, value: String) -> Option<Self> {
+        Some(match name {
+            "contains" => FilterRule::Contains(value),
+            "does-not-contain" => FilterRule::DoesNotContain(value),
+            "is" => FilterRule::Is(value),
+            "is-not" => FilterRule::IsNot(value),
+            "is-empty" => FilterRule::IsEmpty,
+            "is-not-empty" => FilterRule::IsNotEmpty,
+            "greater-than" => FilterRule::GreaterThan(value),
+            "less-than" => FilterRule::LessThan(value),
+            _ => return None,
+        })
+    }
+
+    pub fn value(&self) -> Option<&str> {
+        match self {
+            FilterRule::Contains(value)
+            | FilterRule::DoesNotContain(value)
+            | FilterRule::Is(value)
+            | FilterRule::IsNot(value)
+            | FilterRule::GreaterThan(value)
+            | FilterRule::LessThan(value) => Some(value),
+            FilterRule::IsEmpty | FilterRule::IsNotEmpty => None,
+        }
+    }
+
+    /// Whether a cell of a `kind` column holding `value` passes.
+    /// Rules still waiting for something to compare with let every cell pass.
+    pub fn matches(&self, kind: &ColumnKind, value: Option<&str>) -> bool {
+        if self.value().map_or(false, |v| v.trim().is_empty()) {
+            return true;
+        }
+        let value = value.filter(|v| !v.trim().is_empty());
+        match self {
+            FilterRule::IsEmpty => value.is_none(),
+            FilterRule::IsNotEmpty => value.is_some(),
+            FilterRule::Contains(part) => value.map_or(false, |v| v.to_lowercase().contains(&part.to_lowercase())),
+            FilterRule::DoesNotContain(part) => !FilterRule::Contains(part.clone()).matches(kind, value),
+            FilterRule::Is(other) => match (kind, value) {
+                (ColumnKind::MultiSelect(_), Some(v)) => v.lines().any(|o| o.eq_ignore_ascii_case(other.trim())),
+                (ColumnKind::Number | ColumnKind::Boolean, Some(_)) => compare_values(kind, value, Some(other)).is_eq(),
+                (_, Some(v)) => v.trim().to_lowercase() == other.trim().to_lowercase(),
+                (_, None) => false,
+            },
+            FilterRule::IsNot(other) => !FilterRule::Is(other.clone()).matches(kind, value),
+            FilterRule::GreaterThan(other) => comparable(kind, value, other) && compare_values(kind, value, Some(other)).is_gt(),
+            FilterRule::LessThan(other) => comparable(kind, value, other) && compare_values(kind, value, Some(other)).is_lt(),
+        }
+    }
+}
+
+/// Whether `value` can be ordered against `other`, which for numbers means both are numbers
+fn comparable(kind: &ColumnKind, value: Option<&str>, other: &str) -> bool {
+    match (kind, value) {
+        (_, None) => false,
+        (ColumnKind::Number, Some(value)) => value.trim().parse::<f64>().is_ok() && other.trim().parse::<f64>().is_ok(),
+        _ => true,
+    }
+}
+
+/// Orders two values of a column of `kind`, with empty ones last
+pub fn compare_values(kind: &ColumnKind, a: Option<&str>, b: Option<&str>) -> Ordering {
+    let a = a.map(str::trim).filter(|a| !a.is_empty());
+    let b = b.map(str::trim).filter(|b| !b.is_empty());
+    let (a, b) = match (a, b) {
+        (Some(a), Some(b)) => (a, b),
+        (Some(_), None) => return Ordering::Less,
+        (None, Some(_)) => return Ordering::Greater,
+        (None, None) => return Ordering::Equal,
+    };
+    let text = || a.to_lowercase().cmp(&b.to_lowercase()).then_with(|| a.cmp(b));
+    match kind {
+        ColumnKind::Number => match (a.parse::<f64>(), b.parse::<f64>()) {
+            (Ok(a), Ok(b)) => a.total_cmp(&b),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => text(),
+        },
+        ColumnKind::Boolean => kind.check(a).cmp(&kind.check(b)),
+        ColumnKind::Select(options) => {
+            let position = |v: &str| options.iter().position(|o| o.eq_ignore_ascii_case(v)).unwrap_or(options.len());
+            position(a).cmp(&position(b)).then_with(text)
+        },
+        _ => text(),
+    }
+}
@@ -0,0 +1,1193 @@
+//! Conversion between `TextContent` and CommonMark.
+//! Strikethrough uses the `~~` extension, and links to other files the `papier:` scheme.
+
+use std::cmp::Reverse;
+
+use super::{TextContent, Paragraph, RichText, Mark, MarkKind, ListItem, ListKind, CheckItem, CodeBlock, Attachment};
+
+const FILE_LINK_SCHEME: &str = "papier:";
+
+impl TextContent {
+    /// CommonMark for the content, with `title` as its level 1 heading
+    pub fn to_markdown(&self, title: &str) -> String {
+        let mut md = String::new();
+        if !title.is_empty() {
+            md += "# ";
+            md += &inline_markdown(&RichText::plain(title.to_string()));
+        }
+        // Content columns of the list items the next one can nest under
+        let mut columns: Vec<usize> = Vec::new();
+        let mut counters: Vec<(ListKind, usize)> = Vec::new();
+        let mut was_list = false;
+        for p in &self.paragraphs {
+            let is_list = matches!(p, Paragraph::ListItem(_) | Paragraph::Check(_));
+            if !md.is_empty() {
+                md += if was_list && is_list { "\n" } else { "\n\n" };
+            }
+            was_list = is_list;
+            if !matches!(p, Paragraph::ListItem(_)) {
+                columns.clear();
+                counters.clear();
+            }
+            let block = match p {
+                Paragraph::Text(text) if text.text.is_empty() => "&nbsp;".to_string(),
+                Paragraph::Text(text) => inline_markdown(text),
+                Paragraph::H2(text) => heading_markdown(2, text),
+                Paragraph::H3(text) => heading_markdown(3, text),
+                Paragraph::H4(text) => heading_markdown(4, text),
+                Paragraph::ListItem(item) => {
+                    let indent = item.indent.min(columns.len());
+                    columns.truncate(indent);
+                    counters.truncate(indent + 1);
+                    while counters.len() <= indent {
+                        counters.push((item.kind, 0));
+                    }
+                    if counters[indent].0 != item.kind {
+                        counters[indent] = (item.kind, 0);
+                    }
+                    counters[indent].1 += 1;
+                    let marker = match item.kind {
+                        ListKind::Bullet => "-".to_string(),
+                        ListKind::Numbered => format!("{}.", counters[indent].1),
+                    };
+                    let column = columns.last().copied().unwrap_or(0);
+                    columns.push(column + marker.len() + 1);
+                    " ".repeat(column) + &list_item_markdown(&marker, &item.text, column)
+                },
+                Paragraph::Check(item) => {
+                    let marker = if item.checked { "- [x]" } else { "- [ ]" };
+                    list_item_markdown(marker, &item.text, 0)
+                },
+                Paragraph::Code(block) => {
+                    let longest = block.code.lines()
+                        .map(|line| line.trim_start().chars().take_while(|c| *c == '`').count())
+                        .max()
+                        .unwrap_or(0);
+                    let fence = "`".repeat(longest.max(2) + 1);
+                    format!("{fence}{}\n{}\n{fence}", block.language.as_deref().unwrap_or(""), block.code)
+                },
+                Paragraph::Image(attachment) => "!".to_string() + &attachment_markdown(attachment),
+                Paragraph::Attachment(attachment) => attachment_markdown(attachment),
+            };
+            md += block.trim_end_matches(' ');
+        }
+        md += "\n";
+        md
+    }
+
+    /// Reads CommonMark, returning the title too if the document starts with a level 1 heading.
+    /// Images and attachments keep the path they're linked with.
+    pub fn from_markdown(markdown: &str) -> (Option<String>, Self) {
+        let mut reader = Reader::default();
+        let lines = markdown.lines().map(str::to_string).collect::<Vec<_>>();
+        let mut i = 0;
+        while i < lines.len() {
+            i = reader.read_line(&lines, i);
+        }
+        reader.flush();
+        (reader.title, Self { paragraphs: reader.paragraphs })
+    }
+}
+
+fn heading_markdown(level: usize, text: &str) -> String {
+    let text = inline_markdown(&RichText::plain(text.to_string()));
+    // A trailing # would be read as the optional closing sequence
+    match text.strip_suffix('#') {
+        Some(text) => "#".repeat(level) + " " + text + "\\#",
+        None => "#".repeat(level) + " " + &text,
+    }
+}
+
+/// Continuation lines of the item are indented to line up with its text
+fn list_item_markdown(marker: &str, text: &RichText, column: usize) -> String {
+    let indent = "\n".to_string() + &" ".repeat(column + marker.chars().count() + 1);
+    marker.to_string() + " " + &inline_markdown(text).replace('\n', &indent)
+}
+
+fn attachment_markdown(attachment: &Attachment) -> String {
+    format!("[{}]({})", inline_markdown(&RichText::plain(attachment.name.clone())), destination_markdown(&attachment.path))
+}
+
+fn destination_markdown(href: &str) -> String {
+    let href = href.replace('\\', "\\\\");
+    if href.contains([' ', '(', ')', '<', '>']) {
+        format!("<{}>", href.replace('<', "\\<").replace('>', "\\>"))
+    } else {
+        href
+    }
+}
+
+fn link_href(kind: &MarkKind) -> String {
+    match kind {
+        MarkKind::FileLink(id) => FILE_LINK_SCHEME.to_string() + &id.to_string(),
+        MarkKind::Link(href) => href.clone(),
+        _ => String::new(),
+    }
+}
+
+fn inline_markdown(text: &RichText) -> String {
+    let chars = text.text.chars().collect::<Vec<_>>();
+    let len = chars.len();
+    let mut marks = Vec::new();
+    let mut code = Vec::new();
+    for mark in &text.marks {
+        let (start, end) = (mark.start.min(len), mark.end.min(len));
+        if mark.kind != MarkKind::Code {
+            marks.push(Mark { kind: mark.kind.clone(), start, end });
+            continue;
+        }
+        // Code spans can't hold line breaks
+        let mut s = start;
+        for i in start..=end {
+            if i == end || chars[i] == '\n' {
+                if i > s {
+                    code.push((s, i));
+                }
+                s = i + 1;
+            }
+        }
+    }
+    // Code spans right next to each other would run their backticks together
+    code.sort();
+    let code = code.into_iter().fold(Vec::<(usize, usize)>::new(), |mut merged, (s, e)| {
+        match merged.last_mut() {
+            Some(last) if s <= last.1 => last.1 = last.1.max(e),
+            _ => merged.push((s, e)),
+        }
+        merged
+    });
+    let pieces = nested_pieces(&chars, &marks);
+    let is_emphasis = |k: usize| matches!(marks[pieces[k].0].kind, MarkKind::Bold | MarkKind::Italic | MarkKind::Strikethrough);
+
+    // Closing pieces go before opening ones, inner ones closing first and outer ones opening first
+    let mut events = pieces.iter().enumerate()
+        .flat_map(|(k, (_, start, end))| [(*end, false, k), (*start, true, k)])
+        .collect::<Vec<_>>();
+    events.sort_by_key(|(p, opens, k)| (*p, *opens, if *opens { *k as isize } else { -(*k as isize) }));
+    let mut bounds = events.iter().map(|(p, _, _)| *p)
+        .chain(code.iter().flat_map(|(s, e)| [*s, *e]))
+        .chain(chars.iter().enumerate().filter(|(_, c)| **c == '\n').flat_map(|(i, _)| [i, i + 1]))
+        .chain([0, len])
+        .collect::<Vec<_>>();
+    bounds.sort();
+    bounds.dedup();
+
+    // Whether a letter comes right after the delimiters closing a piece at `end`
+    let closes_before_word = |end: usize| {
+        chars.get(end).map_or(false, |c| c.is_alphanumeric())
+            && pieces.iter().filter(|(_, s, e)| *s == end || *e == end).count() == 1
+            && !code.iter().any(|(s, _)| *s == end)
+    };
+    // Pieces in `tags` are written as HTML tags rather than delimiters
+    let render = |tags: &[bool]| {
+        let mut md = String::new();
+        let mut delimiters = vec!['*'; pieces.len()];
+        let mut events = events.iter().peekable();
+        for (b, &p) in bounds.iter().enumerate() {
+            let mut closed = None;
+            while let Some(&(_, opens, k)) = events.next_if(|(e, _, _)| *e == p) {
+                let kind = &marks[pieces[k].0].kind;
+                if !opens {
+                    md += &match kind {
+                        kind if tags[k] => format!("</{}>", html_tag_name(kind)),
+                        MarkKind::Bold | MarkKind::Italic | MarkKind::Strikethrough => delimiter(kind, delimiters[k]),
+                        kind => format!("]({})", destination_markdown(&link_href(kind))),
+                    };
+                    closed = Some(delimiters[k]).filter(|_| !tags[k]);
+                    continue;
+                }
+                // A `*` run right after the one just closed would merge with it, `_` can't close inside a word though
+                delimiters[k] = match (kind, closed) {
+                    (MarkKind::Strikethrough, _) => '~',
+                    (_, Some('*')) if is_emphasis(k) && !closes_before_word(pieces[k].2) => '_',
+                    _ => '*',
+                };
+                md += &match kind {
+                    kind if tags[k] => format!("<{}>", html_tag_name(kind)),
+                    MarkKind::Bold | MarkKind::Italic | MarkKind::Strikethrough => delimiter(kind, delimiters[k]),
+                    _ => "[".to_string(),
+                };
+                closed = None;
+            }
+            if let Some(&next) = bounds.get(b + 1) {
+                if code.iter().any(|(s, e)| *s <= p && next <= *e) {
+                    md += &code_span(&chars[p..next].iter().collect::<String>());
+                } else {
+                    for i in p..next {
+                        md += &escape_char(&chars, i);
+                    }
+                }
+            }
+        }
+        md
+    };
+
+    // Delimiters only open and close emphasis next to some characters, so what they'd get lost on is written
+    // as HTML tags instead, found by reading it back, one round at a time as one lost piece can throw off others
+    let mut tags = vec![false; pieces.len()];
+    loop {
+        let md = render(&tags);
+        let read = parse_inline(&md);
+        // Delimiters that didn't work are left in the text, which is skipped to find where each character went
+        let read_chars = read.text.chars().collect::<Vec<_>>();
+        let mut at = Vec::with_capacity(len);
+        for c in &chars {
+            let start = at.last().map_or(0, |a| a + 1);
+            match read_chars[start.min(read_chars.len())..].iter().position(|r| r == c) {
+                Some(n) => at.push(start + n),
+                None => break,
+            }
+        }
+        if at.len() == len {
+            let covered = |kind: &MarkKind, i: usize| read.marks.iter().any(|m| m.kind == *kind && m.start <= at[i] && at[i] < m.end);
+            let lost = (0..pieces.len())
+                .filter(|&k| is_emphasis(k) && !tags[k])
+                .filter(|&k| (pieces[k].1..pieces[k].2).any(|i| !covered(&marks[pieces[k].0].kind, i)))
+                .collect::<Vec<_>>();
+            let extra = (0..len).any(|i| {
+                read.marks.iter()
+                    .filter(|m| matches!(m.kind, MarkKind::Bold | MarkKind::Italic | MarkKind::Strikethrough))
+                    .any(|m| m.start <= at[i] && at[i] < m.end && !pieces.iter().any(|(j, s, e)| marks[*j].kind == m.kind && *s <= i && i < *e))
+            });
+            if read.text == text.text && lost.is_empty() && !extra {
+                return md;
+            }
+            if !lost.is_empty() {
+                for k in lost {
+                    tags[k] = true;
+                }
+                continue;
+            }
+        }
+        // Nothing to point at, so all of it
+        tags = (0..pieces.len()).map(is_emphasis).collect();
+        return render(&tags);
+    }
+}
+
+fn html_tag_name(kind: &MarkKind) -> &'static str {
+    match kind {
+        MarkKind::Bold => "b",
+        MarkKind::Italic => "i",
+        _ => "s",
+    }
+}
+
+/// Markdown can only nest marks, so overlapping ones get split up into pieces that nest,
+/// as `(mark, start, end)`. Emphasis can't start or end on whitespace either, so pieces hug the words inside
+/// where the mark itself starts or ends, whitespace where it's only split up is kept.
+fn nested_pieces(chars: &[char], marks: &[Mark]) -> Vec<(usize, usize, usize)> {
+    let mut bounds = marks.iter().flat_map(|m| [m.start, m.end]).collect::<Vec<_>>();
+    bounds.sort();
+    bounds.dedup();
+    let mut pieces: Vec<(usize, usize, usize)> = Vec::new();
+    let mut open: Vec<usize> = Vec::new();
+    for p in bounds {
+        let active = |j: usize| marks[j].start <= p && p < marks[j].end;
+        let mut reopen = Vec::new();
+        while open.iter().any(|&k| !active(pieces[k].0)) {
+            let k = open.pop().unwrap();
+            pieces[k].2 = p;
+            if active(pieces[k].0) {
+                reopen.push(pieces[k].0);
+            }
+        }
+        reopen.extend((0..marks.len()).filter(|&j| marks[j].start == p && marks[j].start < marks[j].end));
+        // Links go around emphasis ending with them, so its delimiters sit next to the text
+        reopen.sort_by_key(|&j| (Reverse(marks[j].end), !matches!(marks[j].kind, MarkKind::Link(_) | MarkKind::FileLink(_))));
+        for j in reopen {
+            pieces.push((j, p, p));
+            open.push(pieces.len() - 1);
+        }
+    }
+
+    // Inner pieces first, so outer ones can be kept around them
+    let mut order = (0..pieces.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&k| (pieces[k].2 - pieces[k].1, Reverse(k)));
+    let original = pieces.clone();
+    let mut kept = vec![true; pieces.len()];
+    for k in order {
+        let (j, start, end) = original[k];
+        if !matches!(marks[j].kind, MarkKind::Bold | MarkKind::Italic | MarkKind::Strikethrough) {
+            continue;
+        }
+        let children = (k + 1..pieces.len())
+            .filter(|&l| kept[l] && start <= original[l].1 && original[l].2 <= end)
+            .collect::<Vec<_>>();
+        let mut trimmed_start = if start == marks[j].start {
+            (start..end).find(|&i| !chars[i].is_whitespace()).unwrap_or(end)
+        } else {
+            start
+        };
+        let mut trimmed_end = if end == marks[j].end {
+            (start..end).rev().find(|&i| !chars[i].is_whitespace()).map_or(start, |i| i + 1)
+        } else {
+            end
+        };
+        for &l in &children {
+            trimmed_start = trimmed_start.min(pieces[l].1);
+            trimmed_end = trimmed_end.max(pieces[l].2);
+        }
+        if trimmed_start < trimmed_end {
+            pieces[k] = (j, trimmed_start, trimmed_end);
+        } else {
+            kept[k] = false;
+        }
+    }
+    pieces.into_iter().zip(kept).filter(|(_, kept)| *kept).map(|(piece, _)| piece).collect()
+}
+
+fn delimiter(kind: &MarkKind, c: char) -> String {
+    match kind {
+        MarkKind::Italic => c.to_string(),
+        _ => c.to_string().repeat(2),
+    }
+}
+
+fn code_span(code: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in code.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let fence = "`".repeat(longest + 1);
+    let pad = code.starts_with('`') || code.ends_with('`')
+        || (code.starts_with(' ') && code.ends_with(' ') && !code.trim().is_empty());
+    if pad {
+        format!("{fence} {code} {fence}")
+    } else {
+        format!("{fence}{code}{fence}")
+    }
+}
+
+/// Escapes the character at `i` if Markdown would read it as syntax, or drop it
+fn escape_char(chars: &[char], i: usize) -> String {
+    let c = chars[i];
+    let line_start = chars[..i].iter().rposition(|c| *c == '\n').map_or(0, |n| n + 1);
+    let line_end = chars[i..].iter().position(|c| *c == '\n').map_or(chars.len(), |n| i + n);
+    let is_space = |c: &char| *c == ' ' || *c == '\t';
+    match c {
+        // A line break ending the paragraph would be dropped
+        '\n' if i + 1 == chars.len() => "&#10;".to_string(),
+        '\n' => "\\\n".to_string(),
+        // Leading and trailing whitespace would be trimmed, or read as indentation and line breaks
+        ' ' | '\t' if chars[line_start..i].iter().all(is_space) || chars[i..line_end].iter().all(is_space) => {
+            format!("&#{};", c as u32)
+        },
+        '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '~' | '!' | '&' => format!("\\{c}"),
+        '#' | '>' | '-' | '+' | '=' if chars[line_start..i].iter().all(is_space) => format!("\\{c}"),
+        '.' | ')' if i > line_start && i - line_start <= 9 && chars[line_start..i].iter().all(char::is_ascii_digit) => {
+            format!("\\{c}")
+        },
+        c => c.to_string(),
+    }
+}
+
+/// Turns the tabs indenting the line into spaces, as they only matter to blocks there, and are kept in the text
+fn expand_indent(line: &str) -> String {
+    let mut expanded = String::new();
+    let mut column = 0;
+    for (i, c) in line.char_indices() {
+        match c {
+            '\t' => {
+                let spaces = 4 - column % 4;
+                expanded += &" ".repeat(spaces);
+                column += spaces;
+            },
+            ' ' => {
+                expanded.push(' ');
+                column += 1;
+            },
+            _ => return expanded + &line[i..],
+        }
+    }
+    expanded
+}
+
+/// Block that may still get more lines
+enum Open {
+    Text,
+    ListItem(ListKind, usize),
+    Check(bool),
+}
+
+#[derive(Default)]
+struct Reader {
+    title: Option<String>,
+    paragraphs: Vec<Paragraph>,
+    open: Option<(Open, Vec<String>)>,
+    /// Marker and content columns of the list items the next one can nest under
+    lists: Vec<(usize, usize)>,
+    blank: bool,
+}
+
+impl Reader {
+    /// Reads the block starting at line `i`, returns where the next one starts
+    fn read_line(&mut self, lines: &[String], i: usize) -> usize {
+        self.read(&expand_indent(&lines[i]), lines, i)
+    }
+
+    fn read(&mut self, line: &str, lines: &[String], i: usize) -> usize {
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        let rest = &line[indent..];
+        if rest.is_empty() {
+            if let Some((Open::Text, _)) = self.open {
+                self.flush();
+            }
+            self.blank = true;
+            return i + 1;
+        }
+        let was_blank = std::mem::replace(&mut self.blank, false);
+        let in_paragraph = !was_blank && self.open.is_some();
+
+        if indent < 4 {
+            // Block quotes don't exist in Papier, their content is kept as it is
+            if let Some(quoted) = rest.strip_prefix('>') {
+                let quoted = quoted.strip_prefix(' ').unwrap_or(quoted);
+                self.blank = was_blank;
+                return self.read(quoted, lines, i);
+            }
+            if let Some(fence) = code_fence(rest) {
+                self.flush();
+                self.lists.clear();
+                return self.read_fenced_code(lines, i, indent, fence);
+            }
+            if let Some((level, text)) = atx_heading(rest) {
+                self.flush();
+                self.lists.clear();
+                self.push_heading(level, text);
+                return i + 1;
+            }
+            if let Some((Open::Text, text)) = &self.open {
+                let underline = rest.trim_end_matches([' ', '\t']);
+                if !was_blank && (underline.chars().all(|c| c == '=') || underline.chars().all(|c| c == '-')) {
+                    let level = if underline.starts_with('=') { 1 } else { 2 };
+                    let text = text.join("\n");
+                    self.open = None;
+                    self.push_heading(level, &text);
+                    return i + 1;
+                }
+            }
+            if is_thematic_break(rest) {
+                self.flush();
+                self.lists.clear();
+                return i + 1;
+            }
+        }
+        if indent < 4 || !self.lists.is_empty() {
+            if let Some((marker, kind, text)) = list_marker(rest) {
+                if !in_paragraph || !text.is_empty() || !matches!(self.open, Some((Open::Text, _))) {
+                    self.flush();
+                    self.push_list_item(indent, marker, kind, text);
+                    return i + 1;
+                }
+            }
+        }
+        if let Some((Open::ListItem(..) | Open::Check(_), item)) = &mut self.open {
+            if !was_blank {
+                item.push(rest.to_string());
+                return i + 1;
+            }
+            if indent >= self.lists.last().map_or(2, |(_, content)| *content) {
+                // A second paragraph in the item, kept as a line break
+                item.last_mut().unwrap().push('\\');
+                item.push(rest.to_string());
+                return i + 1;
+            }
+        }
+        if was_blank || !matches!(self.open, Some((Open::ListItem(..) | Open::Check(_), _))) {
+            self.lists.clear();
+        }
+        if in_paragraph {
+            if let Some((_, text)) = &mut self.open {
+                text.push(rest.to_string());
+                return i + 1;
+            }
+        }
+        self.flush();
+        if indent >= 4 {
+            return self.read_indented_code(&line[4..], lines, i);
+        }
+        self.open = Some((Open::Text, vec![rest.to_string()]));
+        i + 1
+    }
+
+    fn read_fenced_code(&mut self, lines: &[String], i: usize, indent: usize, (fence, info): (String, String)) -> usize {
+        let mut code = Vec::new();
+        let mut j = i + 1;
+        while j < lines.len() {
+            let line = expand_indent(&lines[j]);
+            let trimmed = line.trim_start_matches(' ');
+            let fence_char = fence.chars().next().unwrap();
+            if line.len() - trimmed.len() < 4
+                && trimmed.starts_with(&fence)
+                && trimmed.trim_end().chars().all(|c| c == fence_char) {
+                j += 1;
+                break;
+            }
+            // Only the fence's indentation is taken off, code keeps its tabs
+            let line = &lines[j];
+            let strip = (line.len() - line.trim_start_matches(' ').len()).min(indent);
+            code.push(line[strip..].to_string());
+            j += 1;
+        }
+        let language = info.split_whitespace().next().map(|s| s.to_string());
+        self.paragraphs.push(Paragraph::Code(CodeBlock { language, code: code.join("\n") }));
+        j
+    }
+
+    fn read_indented_code(&mut self, first: &str, lines: &[String], i: usize) -> usize {
+        let mut code = vec![first.to_string()];
+        let mut j = i + 1;
+        while j < lines.len() {
+            let line = expand_indent(&lines[j]);
+            if let Some(line) = line.strip_prefix("    ") {
+                code.push(line.to_string());
+            } else if line.trim_matches([' ', '\t']).is_empty() {
+                code.push(String::new());
+            } else {
+                break;
+            }
+            j += 1;
+        }
+        while code.last().map_or(false, |line| line.is_empty()) {
+            code.pop();
+        }
+        self.paragraphs.push(Paragraph::Code(CodeBlock { language: None, code: code.join("\n") }));
+        j
+    }
+
+    fn push_heading(&mut self, level: usize, text: &str) {
+        let text = parse_inline(text).text;
+        let p = match level {
+            1 if self.title.is_none() && self.paragraphs.is_empty() => {
+                self.title = Some(text);
+                return;
+            },
+            1 | 2 => Paragraph::H2(text),
+            3 => Paragraph::H3(text),
+            _ => Paragraph::H4(text),
+        };
+        self.paragraphs.push(p);
+    }
+
+    fn push_list_item(&mut self, column: usize, marker: usize, kind: ListKind, text: &str) {
+        while self.lists.last().map_or(false, |(_, content)| column < *content) {
+            self.lists.pop();
+        }
+        let indent = self.lists.len();
+        // Five spaces or more after the marker start indented code instead
+        let spaces = match text.len() - text.trim_start().len() {
+            0 | 5.. => 1,
+            spaces => spaces,
+        };
+        self.lists.push((column, column + marker + spaces));
+        let text = text.trim_start();
+        let check = ["[ ]", "[x]", "[X]"].iter()
+            .find(|check| text.starts_with(*check) && text[3..].chars().next().map_or(true, |c| c == ' '));
+        self.open = Some(match check {
+            Some(check) if kind == ListKind::Bullet => {
+                (Open::Check(check != &"[ ]"), vec![text[3..].trim_start().to_string()])
+            },
+            _ => (Open::ListItem(kind, indent), vec![text.to_string()]),
+        });
+    }
+
+    fn flush(&mut self) {
+        let (open, lines) = match self.open.take() {
+            Some(open) => open,
+            None => return,
+        };
+        let raw = lines.join("\n");
+        let p = match open {
+            Open::Text => match lone_link(&raw) {
+                Some((true, name, path)) => Paragraph::Image(Attachment { path, name }),
+                Some((false, name, path)) if !path.contains(':') => Paragraph::Attachment(Attachment { path, name }),
+                // An empty paragraph is written as a lone non-breaking space entity, one holding the character is kept
+                _ if raw.trim_matches([' ', '\t', '\n']) == "&nbsp;" => Paragraph::Text(RichText::default()),
+                _ => Paragraph::Text(parse_inline(&raw)),
+            },
+            Open::ListItem(kind, indent) => Paragraph::ListItem(ListItem { kind, indent, text: parse_inline(&raw) }),
+            Open::Check(checked) => Paragraph::Check(CheckItem { checked, text: parse_inline(&raw) }),
+        };
+        self.paragraphs.push(p);
+    }
+}
+
+/// The fence and info string opening a fenced code block
+fn code_fence(line: &str) -> Option<(String, String)> {
+    let c = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let n = line.chars().take_while(|x| *x == c).count();
+    let info = line[n..].trim();
+    if n < 3 || (c == '`' && info.contains('`')) {
+        return None;
+    }
+    Some((c.to_string().repeat(n), info.to_string()))
+}
+
+fn atx_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let rest = &line[level..];
+    if level == 0 || level > 6 || !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    let text = rest.trim();
+    // An optional closing sequence of #s
+    let closed = text.trim_end_matches('#');
+    let text = if closed.is_empty() || closed.ends_with(' ') { closed.trim_end() } else { text };
+    Some((level, text))
+}
+
+fn is_thematic_break(line: &str) -> bool {
+    let line = line.trim_end();
+    match line.chars().next() {
+        Some(c @ ('-' | '*' | '_')) => {
+            line.chars().all(|x| x == c || x == ' ') && line.chars().filter(|x| *x == c).count() >= 3
+        },
+        _ => false,
+    }
+}
+
+/// Width of the list marker starting the line, the kind of list, and the text after it
+fn list_marker(line: &str) -> Option<(usize, ListKind, &str)> {
+    let (marker, kind) = match line.chars().next()? {
+        '-' | '+' | '*' => (1, ListKind::Bullet),
+        '0'..='9' => {
+            let digits = line.chars().take_while(char::is_ascii_digit).count();
+            match line[digits..].chars().next() {
+                Some('.' | ')') if digits <= 9 => (digits + 1, ListKind::Numbered),
+                _ => return None,
+            }
+        },
+        _ => return None,
+    };
+    let rest = &line[marker..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    Some((marker, kind, rest.trim_end()))
+}
+
+/// `![name](path)` or `[name](path)` making up the whole paragraph, with whether it's an image
+fn lone_link(raw: &str) -> Option<(bool, String, String)> {
+    let raw = raw.trim();
+    let (image, rest) = match raw.strip_prefix("![") {
+        Some(rest) => (true, rest),
+        None => (false, raw.strip_prefix('[')?),
+    };
+    let chars = rest.chars().collect::<Vec<_>>();
+    let mut i = 0;
+    let mut depth = 0;
+    loop {
+        match *chars.get(i)? {
+            '\\' => i += 1,
+            '[' => depth += 1,
+            ']' if depth == 0 => break,
+            ']' => depth -= 1,
+            _ => (),
+        }
+        i += 1;
+    }
+    let (href, end) = link_destination(&chars, i + 1)?;
+    if end != chars.len() {
+        return None;
+    }
+    let label = chars[..i].iter().collect::<String>();
+    Some((image, parse_inline(&label).text, href))
+}
+
+/// Reads `(destination "title")` starting at `i`, returns the destination and where the link ends
+fn link_destination(chars: &[char], i: usize) -> Option<(String, usize)> {
+    if chars.get(i) != Some(&'(') {
+        return None;
+    }
+    let skip_spaces = |mut i: usize| {
+        while chars.get(i).map_or(false, |c| c.is_whitespace()) {
+            i += 1;
+        }
+        i
+    };
+    let mut i = skip_spaces(i + 1);
+    let mut href = String::new();
+    if chars.get(i) == Some(&'<') {
+        i += 1;
+        loop {
+            match *chars.get(i)? {
+                '>' => break,
+                '\n' | '<' => return None,
+                '\\' if chars.get(i + 1).map_or(false, char::is_ascii_punctuation) => {
+                    href.push(chars[i + 1]);
+                    i += 1;
+                },
+                c => href.push(c),
+            }
+            i += 1;
+        }
+        i += 1;
+    } else {
+        let mut depth = 0;
+        while let Some(&c) = chars.get(i) {
+            match c {
+                c if c.is_whitespace() => break,
+                '(' => depth += 1,
+                ')' if depth == 0 => break,
+                ')' => depth -= 1,
+                '\\' if chars.get(i + 1).map_or(false, char::is_ascii_punctuation) => {
+                    href.push(chars[i + 1]);
+                    i += 2;
+                    continue;
+                },
+                _ => (),
+            }
+            href.push(c);
+            i += 1;
+        }
+    }
+    i = skip_spaces(i);
+    if let Some(close) = match chars.get(i) {
+        Some('"') => Some('"'),
+        Some('\'') => Some('\''),
+        Some('(') => Some(')'),
+        _ => None,
+    } {
+        i += 1;
+        while *chars.get(i)? != close {
+            if chars[i] == '\\' {
+                i += 1;
+            }
+            i += 1;
+        }
+        i = skip_spaces(i + 1);
+    }
+    if chars.get(i) != Some(&')') {
+        return None;
+    }
+    Some((href, i + 1))
+}
+
+fn link_kind(href: String) -> MarkKind {
+    match href.strip_prefix(FILE_LINK_SCHEME).and_then(|id| id.parse().ok()) {
+        Some(id) => MarkKind::FileLink(id),
+        None => MarkKind::Link(href),
+    }
+}
+
+fn decode_entity(chars: &[char], i: usize) -> Option<(char, usize)> {
+    let end = i + chars[i..].iter().take(32).position(|c| *c == ';')?;
+    let name = chars[i + 1..end].iter().collect::<String>();
+    let c = match name.as_str() {
+        "nbsp" => '\u{a0}',
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        _ => {
+            let code = match name.strip_prefix('#')? {
+                hex if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16).ok()?,
+                dec => dec.parse().ok()?,
+            };
+            char::from_u32(code).filter(|c| *c != '\0').unwrap_or('\u{fffd}')
+        },
+    };
+    Some((c, end + 1))
+}
+
+/// A run of `*`, `_` or `~` that may open or close a mark
+struct Delimiter {
+    node: usize,
+    c: char,
+    count: usize,
+    original: usize,
+    can_open: bool,
+    can_close: bool,
+}
+
+struct Bracket {
+    node: usize,
+    image: bool,
+    active: bool,
+    /// Delimiters before the bracket, which marks inside the link can't use
+    bottom: usize,
+}
+
+/// Inline content as pieces of text with marks over ranges of pieces,
+/// since delimiter runs only turn into marks or text once the whole paragraph is read
+#[derive(Default)]
+struct InlineParser {
+    nodes: Vec<String>,
+    marks: Vec<(MarkKind, usize, usize)>,
+    delimiters: Vec<Delimiter>,
+    brackets: Vec<Bracket>,
+    /// HTML tags opening marks not closed yet, with their node
+    tags: Vec<(MarkKind, usize)>,
+}
+
+impl InlineParser {
+    fn push(&mut self, text: String) -> usize {
+        self.nodes.push(text);
+        self.nodes.len() - 1
+    }
+
+    fn process_emphasis(&mut self, bottom: usize) {
+        let mut c = bottom;
+        while c < self.delimiters.len() {
+            let closer = &self.delimiters[c];
+            if !closer.can_close {
+                c += 1;
+                continue;
+            }
+            let opener = (bottom..c).rev().find(|&o| {
+                let opener = &self.delimiters[o];
+                opener.c == closer.c && opener.can_open
+                    && !(opener.c == '~' && opener.count != closer.count)
+                    && !((opener.can_close || closer.can_open)
+                        && (opener.original + closer.original) % 3 == 0
+                        && !(opener.original % 3 == 0 && closer.original % 3 == 0))
+            });
+            let o = match opener {
+                Some(o) => o,
+                None => {
+                    c += 1;
+                    continue;
+                },
+            };
+            let used = if closer.c == '~' || (self.delimiters[o].count >= 2 && closer.count >= 2) { 2.min(closer.count) } else { 1 };
+            let kind = match (closer.c, used) {
+                ('~', _) => MarkKind::Strikethrough,
+                (_, 2) => MarkKind::Bold,
+                _ => MarkKind::Italic,
+            };
+            self.marks.push((kind, self.delimiters[o].node + 1, closer.node));
+            for d in [o, c] {
+                let delimiter = &mut self.delimiters[d];
+                delimiter.count -= used;
+                self.nodes[delimiter.node] = delimiter.c.to_string().repeat(delimiter.count);
+            }
+            self.delimiters.drain(o + 1..c);
+            c = o + 1;
+            if self.delimiters[c].count == 0 {
+                self.delimiters.remove(c);
+            }
+            if self.delimiters[o].count == 0 {
+                self.delimiters.remove(o);
+                c -= 1;
+            }
+        }
+        self.delimiters.truncate(bottom);
+    }
+
+    fn finish(mut self) -> RichText {
+        self.process_emphasis(0);
+        let mut offsets = Vec::with_capacity(self.nodes.len() + 1);
+        let mut text = String::new();
+        let mut offset = 0;
+        for node in &self.nodes {
+            offsets.push(offset);
+            offset += node.chars().count();
+            text += node;
+        }
+        offsets.push(offset);
+        let mut marks = self.marks.into_iter()
+            .map(|(kind, start, end)| Mark { kind, start: offsets[start], end: offsets[end] })
+            .filter(|m| m.start < m.end)
+            .collect::<Vec<_>>();
+        marks.sort_by_key(|m| (mark_rank(&m.kind), m.start));
+        // Marks split to get around nesting rules join back up
+        let mut merged: Vec<Mark> = Vec::new();
+        for mark in marks {
+            match merged.last_mut() {
+                Some(last) if last.kind == mark.kind && mark.start <= last.end => last.end = last.end.max(mark.end),
+                _ => merged.push(mark),
+            }
+        }
+        merged.sort_by_key(|m| (m.start, mark_rank(&m.kind)));
+        RichText { text, marks: merged }
+    }
+}
+
+fn mark_rank(kind: &MarkKind) -> usize {
+    match kind {
+        MarkKind::Bold => 0,
+        MarkKind::Italic => 1,
+        MarkKind::Strikethrough => 2,
+        MarkKind::Code => 3,
+        MarkKind::Link(_) => 4,
+        MarkKind::FileLink(_) => 5,
+    }
+}
+
+fn parse_inline(markdown: &str) -> RichText {
+    // Only spaces and tabs, as other whitespace like non-breaking spaces is kept
+    let chars = markdown.trim_end_matches([' ', '\t', '\n']).chars().collect::<Vec<_>>();
+    let mut parser = InlineParser::default();
+    let mut text = String::new();
+    let mut i = 0;
+    macro_rules! flush_text {
+        () => {
+            if !text.is_empty() {
+                parser.push(std::mem::take(&mut text));
+            }
+        };
+    }
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' if chars.get(i + 1) == Some(&'\n') => {
+                text.push('\n');
+                i += 2;
+                while chars.get(i) == Some(&' ') {
+                    i += 1;
+                }
+            },
+            '\\' if chars.get(i + 1).map_or(false, char::is_ascii_punctuation) => {
+                text.push(chars[i + 1]);
+                i += 2;
+            },
+            '\n' => {
+                let hard = text.ends_with("  ");
+                text.truncate(text.trim_end_matches(' ').len());
+                text.push(if hard { '\n' } else { ' ' });
+                i += 1;
+                while chars.get(i) == Some(&' ') {
+                    i += 1;
+                }
+            },
+            '&' => match decode_entity(&chars, i) {
+                Some((decoded, end)) => {
+                    text.push(decoded);
+                    i = end;
+                },
+                None => {
+                    text.push('&');
+                    i += 1;
+                },
+            },
+            '`' => {
+                let n = chars[i..].iter().take_while(|c| **c == '`').count();
+                let mut j = i + n;
+                let mut close = None;
+                while j < chars.len() {
+                    let run = chars[j..].iter().take_while(|c| **c == '`').count();
+                    if run == n {
+                        close = Some(j);
+                        break;
+                    }
+                    j += run.max(1);
+                }
+                match close {
+                    Some(j) => {
+                        let mut code = chars[i + n..j].iter()
+                            .map(|c| if *c == '\n' { ' ' } else { *c })
+                            .collect::<String>();
+                        if code.len() >= 2 && code.starts_with(' ') && code.ends_with(' ') && !code.trim().is_empty() {
+                            code = code[1..code.len() - 1].to_string();
+                        }
+                        flush_text!();
+                        let node = parser.push(code);
+                        parser.marks.push((MarkKind::Code, node, node + 1));
+                        i = j + n;
+                    },
+                    None => {
+                        text += &"`".repeat(n);
+                        i += n;
+                    },
+                }
+            },
+            '*' | '_' | '~' => {
+                let n = chars[i..].iter().take_while(|x| **x == c).count();
+                let before = if i == 0 { ' ' } else { chars[i - 1] };
+                let after = chars.get(i + n).copied().unwrap_or(' ');
+                let left = !after.is_whitespace()
+                    && (!is_punctuation(after) || before.is_whitespace() || is_punctuation(before));
+                let right = !before.is_whitespace()
+                    && (!is_punctuation(before) || after.is_whitespace() || is_punctuation(after));
+                let (can_open, can_close) = match c {
+                    '_' => (left && (!right || is_punctuation(before)), right && (!left || is_punctuation(after))),
+                    '~' if n > 2 => (false, false),
+                    _ => (left, right),
+                };
+                flush_text!();
+                let node = parser.push(c.to_string().repeat(n));
+                parser.delimiters.push(Delimiter { node, c, count: n, original: n, can_open, can_close });
+                i += n;
+            },
+            '!' if chars.get(i + 1) == Some(&'[') => {
+                flush_text!();
+                let node = parser.push("![".to_string());
+                parser.brackets.push(Bracket { node, image: true, active: true, bottom: parser.delimiters.len() });
+                i += 2;
+            },
+            '[' => {
+                flush_text!();
+                let node = parser.push("[".to_string());
+                parser.brackets.push(Bracket { node, image: false, active: true, bottom: parser.delimiters.len() });
+                i += 1;
+            },
+            ']' => {
+                let link = match parser.brackets.last() {
+                    Some(bracket) if bracket.active => link_destination(&chars, i + 1),
+                    _ => None,
+                };
+                match link {
+                    Some((href, end)) => {
+                        flush_text!();
+                        let bracket = parser.brackets.pop().unwrap();
+                        parser.process_emphasis(bracket.bottom);
+                        parser.nodes[bracket.node].clear();
+                        parser.marks.push((link_kind(href), bracket.node + 1, parser.nodes.len()));
+                        // Links can't hold other links
+                        if !bracket.image {
+                            for bracket in &mut parser.brackets {
+                                if !bracket.image {
+                                    bracket.active = false;
+                                }
+                            }
+                        }
+                        i = end;
+                    },
+                    None => {
+                        parser.brackets.pop();
+                        text.push(']');
+                        i += 1;
+                    },
+                }
+            },
+            '<' => {
+                // Emphasis written as HTML, where delimiters wouldn't work
+                if let Some((kind, closes, end)) = html_tag(&chars, i) {
+                    let open = parser.tags.iter().rposition(|(k, _)| *k == kind);
+                    match (closes, open) {
+                        (false, _) => {
+                            flush_text!();
+                            let node = parser.push(chars[i..end].iter().collect());
+                            parser.tags.push((kind, node));
+                            i = end;
+                            continue;
+                        },
+                        (true, Some(open)) => {
+                            flush_text!();
+                            let (kind, node) = parser.tags.remove(open);
+                            parser.nodes[node].clear();
+                            parser.marks.push((kind, node + 1, parser.nodes.len()));
+                            i = end;
+                            continue;
+                        },
+                        // Closing nothing, so it's just text
+                        (true, None) => (),
+                    }
+                }
+                let end = chars[i + 1..].iter()
+                    .position(|c| *c == '>' || *c == '<' || c.is_whitespace())
+                    .map(|n| i + 1 + n)
+                    .filter(|&end| chars[end] == '>');
+                let href = end.map(|end| chars[i + 1..end].iter().collect::<String>())
+                    .filter(|href| is_absolute_uri(href));
+                match (href, end) {
+                    (Some(href), Some(end)) => {
+                        flush_text!();
+                        let node = parser.push(href.clone());
+                        parser.marks.push((link_kind(href), node, node + 1));
+                        i = end + 1;
+                    },
+                    _ => {
+                        text.push('<');
+                        i += 1;
+                    },
+                }
+            },
+            c => {
+                text.push(c);
+                i += 1;
+            },
+        }
+    }
+    flush_text!();
+    parser.finish()
+}
+
+/// An HTML tag for emphasis starting at `i`, as its mark, whether it closes it, and where it ends
+fn html_tag(chars: &[char], i: usize) -> Option<(MarkKind, bool, usize)> {
+    let end = i + chars[i..].iter().take(10).position(|c| *c == '>')?;
+    let tag = chars[i + 1..end].iter().collect::<String>().to_lowercase();
+    let (closes, name) = match tag.strip_prefix('/') {
+        Some(name) => (true, name),
+        None => (false, tag.as_str()),
+    };
+    let kind = match name {
+        "b" | "strong" => MarkKind::Bold,
+        "i" | "em" => MarkKind::Italic,
+        "s" | "del" | "strike" => MarkKind::Strikethrough,
+        _ => return None,
+    };
+    Some((kind, closes, end + 1))
+}
+
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation() || (!c.is_alphanumeric() && !c.is_whitespace() && !c.is_control())
+}
+
+fn is_absolute_uri(href: &str) -> bool {
+    match href.split_once(':') {
+        Some((scheme, _)) => {
+            (2..=32).contains(&scheme.len())
+                && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+.-".contains(c))
+        },
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str, marks: &[(MarkKind, usize, usize)]) -> Paragraph {
+        let marks = marks.iter().map(|(kind, start, end)| Mark { kind: kind.clone(), start: *start, end: *end }).collect();
+        Paragraph::Text(RichText { text: text.to_string(), marks })
+    }
+
+    /// Writes the paragraphs out and reads them back, returning the markdown in between
+    fn round_trip(paragraphs: Vec<Paragraph>) -> String {
+        let content = TextContent { paragraphs };
+        let md = content.to_markdown("");
+        assert_eq!(TextContent::from_markdown(&md).1, content, "{md}");
+        md
+    }
+
+    #[test]
+    fn emphasis_between_words_and_punctuation() {
+        round_trip(vec![text("foo(bar)baz", &[(MarkKind::Bold, 3, 8)])]);
+        round_trip(vec![text("a.b.c", &[(MarkKind::Italic, 1, 4), (MarkKind::Strikethrough, 2, 3)])]);
+        round_trip(vec![text("x(`y`)z", &[(MarkKind::Italic, 1, 6), (MarkKind::Code, 2, 5)])]);
+        round_trip(vec![text("a\tb c", &[(MarkKind::Bold, 0, 3), (MarkKind::Link("https://x".to_string()), 2, 5)])]);
+        assert_eq!(round_trip(vec![text("foo bar baz", &[(MarkKind::Bold, 4, 7)])]), "foo **bar** baz\n");
+    }
+
+    #[test]
+    fn overlapping_code() {
+        let content = TextContent { paragraphs: vec![text("  ! ~.a", &[(MarkKind::Code, 0, 5), (MarkKind::Code, 3, 7), (MarkKind::Bold, 4, 6)])] };
+        let (_, read) = TextContent::from_markdown(&content.to_markdown(""));
+        assert_eq!(read.paragraphs, vec![text("  ! ~.a", &[(MarkKind::Code, 0, 7), (MarkKind::Bold, 4, 6)])]);
+        round_trip(vec![text("\u{a0} a\n\u{a0}", &[(MarkKind::Code, 0, 3), (MarkKind::Italic, 2, 3)])]);
+    }
+
+    #[test]
+    fn html_emphasis() {
+        let (_, content) = TextContent::from_markdown("a<b>b<i>c</i></b><s>d</s> <u>e</u>");
+        assert_eq!(content.paragraphs, vec![text("abcd <u>e</u>", &[(MarkKind::Bold, 1, 3), (MarkKind::Italic, 2, 3), (MarkKind::Strikethrough, 3, 4)])]);
+    }
+
+    #[test]
+    fn tabs() {
+        round_trip(vec![text("a\tb\t", &[])]);
+        let md = round_trip(vec![Paragraph::Code(CodeBlock { language: None, code: "\tif a {\n\t\tb\t// c\n\t}".to_string() })]);
+        assert!(md.contains("\t\tb\t// c"), "{md}");
+    }
+
+    #[test]
+    fn blank_paragraphs() {
+        round_trip(vec![text("a", &[]), text("", &[]), text("\u{a0}", &[]), text("\u{a0}\u{a0}b\u{a0}", &[])]);
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::HashSet;
+
+use super::FileID;
+
+mod markdown;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TextContent {
+    pub paragraphs: Vec<Paragraph>,
+}
+
+impl TextContent {
+    /// The files this one links to
+    pub fn file_links(&self) -> HashSet<FileID> {
+        self.paragraphs.iter()
+            .filter_map(|p| p.rich_text())
+            .flat_map(|text| text.marks.iter())
+            .filter_map(|mark| match mark.kind {
+                MarkKind::FileLink(id) => Some(id),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Paragraph {
+    Text(RichText),
+    H2(String),
+    H3(String),
+    H4(String),
+    ListItem(ListItem),
+    Check(CheckItem),
+    Code(CodeBlock),
+    Image(Attachment),
+    Attachment(Attachment),
+}
+
+impl Paragraph {
+    pub fn rich_text(&self) -> Option<&RichText> {
+        match self {
+            Paragraph::Text(text) => Some(text),
+            Paragraph::ListItem(item) => Some(&item.text),
+            Paragraph::Check(item) => Some(&item.text),
+            _ => None,
+        }
+    }
+}
+
+/// One item of a list, its number is worked out from the items around it
+#[derive(Debug, PartialEq, Eq)]
+pub struct ListItem {
+    pub kind: ListKind,
+    pub indent: usize,
+    pub text: RichText,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListKind {
+    Bullet,
+    Numbered,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CheckItem {
+    pub checked: bool,
+    pub text: RichText,
+}
+
+/// Preformatted text, kept exactly as typed
+#[derive(Debug, PartialEq, Eq)]
+pub struct CodeBlock {
+    pub language: Option<String>,
+    pub code: String,
+}
+
+/// A file copied into the text file's `assets/` folder,
+/// `path` is relative to the text file's own directory
+#[derive(Debug, PartialEq, Eq)]
+pub struct Attachment {
+    pub path: String,
+    pub name: String,
+}
+
+/// Plain text with formatting marks laid over ranges of it.
+/// Offsets are in characters, the same unit `TextBuffer` uses.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RichText {
+    pub text: String,
+    pub marks: Vec<Mark>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mark {
+    pub kind: MarkKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkKind {
+    Bold,
+    Italic,
+    Strikethrough,
+    Code,
+    Link(String),
+    FileLink(FileID),
+}
+
+impl RichText {
+    pub fn plain(text: String) -> Self {
+        Self { text, marks: vec![] }
+    }
+}
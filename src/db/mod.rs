@@ -1,8 +1,8 @@
-use std::collections::HashMap;
+use std::{collections::{HashMap, HashSet}, fs, io, path::Path};
 
 use rand::random;
 
-use crate::data::{FileID, FileDisplay, text::TextContent, Category, table::TableContent};
+use crate::data::{FileID, FileDisplay, text::{TextContent, Paragraph, RichText, Mark, MarkKind}, Category, table::{TableContent, csv::detect_delimiter}};
 
 mod saveable;
 mod store;
@@ -12,14 +12,29 @@ pub use saveable::*;
 pub struct DB {
     pub root: String,
     files: HashMap<FileID, FileDisplay>,
+    /// For each file, the text files linking to it
+    backlinks: HashMap<FileID, HashSet<FileID>>,
+    /// For each text file, its unchecked checklist items
+    todos: HashMap<FileID, Vec<String>>,
 }
 
 impl DB {
     pub fn load(root: String) -> Self {
-        Self {
+        let mut db = Self {
             root: root.clone(),
-            files: store::load_files(root)
+            files: store::load_files(root),
+            backlinks: HashMap::new(),
+            todos: HashMap::new(),
+        };
+        for id in db.ids() {
+            if let Category::Text = db.files[&id].category {
+                if let Some(content) = store::load_text_content(db.root.clone(), id) {
+                    db.update_backlinks(id, &content);
+                    db.update_todos(id, &content);
+                }
+            }
         }
+        db
     }
     pub fn ids(&self) -> Vec<FileID> {
         self.files.keys().copied().collect()
@@ -45,6 +60,99 @@ impl DB {
             content
         }
     }
+    pub fn set_text_content(&mut self, id: FileID, content: &TextContent) {
+        store::store_note_content(self.root.clone(), id, content);
+        self.update_backlinks(id, content);
+        self.update_todos(id, content);
+    }
+    pub fn set_table_content(&self, id: FileID, content: &TableContent) {
+        store::store_table_content(self.root.clone(), id, content);
+    }
+    /// The files linking to `id`, sorted by title
+    pub fn backlinks(&self, id: FileID) -> Vec<FileID> {
+        let mut sources = self.backlinks.get(&id)
+            .map_or(vec![], |sources| sources.iter().copied().filter(|s| self.files.contains_key(s)).collect());
+        sources.sort_by_key(|s| self.files[s].title.to_lowercase());
+        sources
+    }
+    /// Every unchecked checklist item in the saved text files
+    pub fn open_todos(&self) -> Vec<(FileID, String)> {
+        self.files.keys()
+            .filter_map(|id| self.todos.get(id).map(|todos| (id, todos)))
+            .flat_map(|(id, todos)| todos.iter().map(|text| (*id, text.clone())))
+            .collect()
+    }
+    pub fn import_asset(&self, id: FileID, source: &Path) -> Option<String> {
+        store::store_asset(self.root.clone(), id, source)
+    }
+    pub fn new_asset_path(&self, id: FileID, name: &str) -> String {
+        store::new_asset_path(self.root.clone(), id, name)
+    }
+    pub fn asset_path(&self, id: FileID, path: &str) -> String {
+        store::asset_path(self.root.clone(), id, path)
+    }
+    pub fn file_dir(&self, id: FileID) -> String {
+        store::file_dir(self.root.clone(), id)
+    }
+    /// Writes the text file as Markdown to `path`, with its assets copied next to it
+    pub fn export_markdown(&self, id: FileID, path: &Path) -> io::Result<()> {
+        let content = self.get_text_content(id);
+        let title = self.files.get(&id).map_or("", |d| d.title.as_str());
+        fs::write(path, content.to_markdown(title))?;
+        let dir = path.parent().unwrap_or(Path::new("."));
+        for p in &content.paragraphs {
+            if let Paragraph::Image(a) | Paragraph::Attachment(a) = p {
+                let target = dir.join(&a.path);
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(self.asset_path(id, &a.path), target)?;
+            }
+        }
+        Ok(())
+    }
+    /// Creates a text file from the Markdown file at `path`, copying the local images and attachments it refers to
+    pub fn import_markdown(&mut self, path: &Path) -> io::Result<FileID> {
+        let (title, mut content) = TextContent::from_markdown(&fs::read_to_string(path)?);
+        let title = title.unwrap_or_else(|| path.file_stem()
+            .map_or("Imported Text".to_string(), |s| s.to_string_lossy().to_string()));
+        let id = self.new_file(title, Category::Text);
+        let dir = path.parent().unwrap_or(Path::new("."));
+        for p in &mut content.paragraphs {
+            if let Paragraph::Image(a) | Paragraph::Attachment(a) = p {
+                match self.import_asset(id, &dir.join(&a.path)) {
+                    Some(imported) => a.path = imported,
+                    None => {
+                        let end = a.name.chars().count();
+                        *p = Paragraph::Text(RichText {
+                            marks: vec![Mark { kind: MarkKind::Link(a.path.clone()), start: 0, end }],
+                            text: a.name.clone(),
+                        });
+                    },
+                }
+            }
+        }
+        self.set_text_content(id, &content);
+        Ok(id)
+    }
+    /// Writes the table to `path` with its columns as the header row
+    pub fn export_table(&self, id: FileID, path: &Path, delimiter: char) -> io::Result<()> {
+        fs::write(path, self.get_table_content(id).to_csv(delimiter))
+    }
+    /// Creates a table file from the CSV or TSV file at `path`
+    pub fn import_table(&mut self, path: &Path) -> io::Result<FileID> {
+        let csv = fs::read_to_string(path)?;
+        let delimiter = match path.extension().and_then(|e| e.to_str()) {
+            Some("tsv" | "tab") => '\t',
+            _ => detect_delimiter(&csv),
+        };
+        let content = TableContent::from_csv(&csv, delimiter);
+        let title = path.file_stem()
+            .map_or("Imported Table".to_string(), |s| s.to_string_lossy().to_string());
+        let id = self.new_file(title, Category::Table);
+        self.set_table_content(id, &content);
+        Ok(id)
+    }
     pub fn new_file(&mut self, title: String, category: Category) -> FileID {
         let id = self.gen_id();
         let d = FileDisplay { title, category, keywords: vec![] };
@@ -62,6 +170,26 @@ impl DB {
         d.keywords = keywords;
         store::store_file_display(self.root.clone(), id, &d);
     }
+    fn update_backlinks(&mut self, source: FileID, content: &TextContent) {
+        let links = content.file_links();
+        for (target, sources) in self.backlinks.iter_mut() {
+            if !links.contains(target) {
+                sources.remove(&source);
+            }
+        }
+        for target in links {
+            self.backlinks.entry(target).or_default().insert(source);
+        }
+    }
+    fn update_todos(&mut self, id: FileID, content: &TextContent) {
+        let todos = content.paragraphs.iter()
+            .filter_map(|p| match p {
+                Paragraph::Check(item) if !item.checked => Some(item.text.text.clone()),
+                _ => None,
+            })
+            .collect();
+        self.todos.insert(id, todos);
+    }
     fn gen_id(&self) -> FileID {
         let id = FileID(random::<u64>());
         if self.files.contains_key(&id) {
@@ -1,19 +1,19 @@
 use crate::data::{FileID, text::TextContent, table::TableContent};
 
-use super::store;
+use super::DB;
 
 pub trait Saveable {
-    fn save(&self, root: String, id: FileID);
+    fn save(&self, db: &mut DB, id: FileID);
 }
 
 impl Saveable for TextContent {
-    fn save(&self, root: String, id: FileID) {
-        store::store_note_content(root, id, self);
+    fn save(&self, db: &mut DB, id: FileID) {
+        db.set_text_content(id, self);
     }
 }
 
 impl Saveable for TableContent {
-    fn save(&self, root: String, id: FileID) {
-        store::store_table_content(root, id, self);
+    fn save(&self, db: &mut DB, id: FileID) {
+        db.set_table_content(id, self);
     }
-}
\ No newline at end of file
+}
@@ -1,6 +1,6 @@
-use std::{fs::{create_dir_all, read_to_string, read_dir}, collections::HashMap};
+use std::{fs::{create_dir_all, read_to_string, read_dir}, collections::HashMap, path::Path};
 use json::{object, JsonValue};
-use crate::data::{FileID, FileDisplay, Category, text::{TextContent, Paragraph}, table::{TableContent, column::Column, TableCell}};
+use crate::data::{FileID, FileDisplay, Category, text::{TextContent, Paragraph, RichText, Mark, MarkKind, ListItem, ListKind, CheckItem, CodeBlock, Attachment}, table::{TableContent, Layout, aggregate::Aggregate, chart::{Chart, ChartKind}, pivot::Pivot, column::{Column, ColumnKind}, view::{Filter, FilterRule, SortKey}, TableCell}};
 
 pub fn load_files(root: String) -> HashMap<FileID, FileDisplay> {
     match read_dir(root + "/files") {
@@ -51,10 +51,25 @@ pub fn load_text_content(root: String, id: FileID) -> Option<TextContent> {
     let c = match json {
         JsonValue::Array(paragraphs) => TextContent {
             paragraphs: paragraphs.into_iter().filter_map(|json| match json["type"].as_str()? {
-                "p" => Some(Paragraph::Text(json["text"].to_string())),
+                "p" => Some(Paragraph::Text(load_rich_text(&json))),
                 "h2" => Some(Paragraph::H2(json["text"].to_string())),
                 "h3" => Some(Paragraph::H3(json["text"].to_string())),
                 "h4" => Some(Paragraph::H4(json["text"].to_string())),
+                "ul" | "ol" => Some(Paragraph::ListItem(ListItem {
+                    kind: if json["type"] == "ol" { ListKind::Numbered } else { ListKind::Bullet },
+                    indent: json["indent"].as_usize().unwrap_or(0),
+                    text: load_rich_text(&json),
+                })),
+                "check" => Some(Paragraph::Check(CheckItem {
+                    checked: json["checked"].as_bool().unwrap_or(false),
+                    text: load_rich_text(&json),
+                })),
+                "code" => Some(Paragraph::Code(CodeBlock {
+                    language: json["language"].as_str().map(|s| s.to_string()),
+                    code: json["text"].to_string(),
+                })),
+                "image" => Some(Paragraph::Image(load_attachment(&json)?)),
+                "attachment" => Some(Paragraph::Attachment(load_attachment(&json)?)),
                 _ => None
             }).collect()
         },
@@ -67,14 +82,127 @@ pub fn store_note_content(root: String, id: FileID, note: &TextContent) {
     let dir = root + "/files/" + id.to_string().as_str();
     let _ = create_dir_all(&dir);
     let json = JsonValue::Array(note.paragraphs.iter().map(|x| match x {
-        Paragraph::Text(text) => object! { "type": "p", text: text.to_string() },
+        Paragraph::Text(text) => store_rich_text("p", text),
         Paragraph::H2(text) => object! { "type": "h2", text: text.to_string() },
         Paragraph::H3(text) => object! { "type": "h3", text: text.to_string() },
         Paragraph::H4(text) => object! { "type": "h4", text: text.to_string() },
+        Paragraph::ListItem(item) => {
+            let mut json = store_rich_text(match item.kind {
+                ListKind::Bullet => "ul",
+                ListKind::Numbered => "ol",
+            }, &item.text);
+            json["indent"] = item.indent.into();
+            json
+        },
+        Paragraph::Check(item) => {
+            let mut json = store_rich_text("check", &item.text);
+            json["checked"] = item.checked.into();
+            json
+        },
+        Paragraph::Code(block) => {
+            let mut json = object! { "type": "code", text: block.code.as_str() };
+            if let Some(language) = &block.language {
+                json["language"] = language.as_str().into();
+            }
+            json
+        },
+        Paragraph::Image(a) => object! { "type": "image", path: a.path.as_str(), name: a.name.as_str() },
+        Paragraph::Attachment(a) => object! { "type": "attachment", path: a.path.as_str(), name: a.name.as_str() },
     }).collect());
     let _ = std::fs::write(dir + "/content.json", json.to_string());
 }
 
+fn load_attachment(json: &JsonValue) -> Option<Attachment> {
+    let path = json["path"].as_str()?.to_string();
+    let name = json["name"].as_str().map(|s| s.to_string())
+        .unwrap_or_else(|| path.rsplit('/').next().unwrap().to_string());
+    Some(Attachment { path, name })
+}
+
+/// Copies `source` into the file's `assets/` folder under a name that isn't taken yet,
+/// and returns its path relative to the file's directory
+pub fn store_asset(root: String, id: FileID, source: &Path) -> Option<String> {
+    let name = source.file_name()?.to_str()?;
+    let path = new_asset_path(root.clone(), id, name);
+    std::fs::copy(source, asset_path(root, id, &path)).ok()?;
+    Some(path)
+}
+
+pub fn new_asset_path(root: String, id: FileID, name: &str) -> String {
+    let dir = root + "/files/" + id.to_string().as_str();
+    let _ = create_dir_all(dir.clone() + "/assets");
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, ".".to_string() + extension),
+        _ => (name, String::new()),
+    };
+    let mut path = "assets/".to_string() + name;
+    let mut i = 1;
+    while Path::new(&(dir.clone() + "/" + &path)).exists() {
+        path = format!("assets/{stem}-{i}{extension}");
+        i += 1;
+    }
+    path
+}
+
+pub fn asset_path(root: String, id: FileID, path: &str) -> String {
+    file_dir(root, id) + "/" + path
+}
+
+/// The directory of the file's content, which asset paths are relative to
+pub fn file_dir(root: String, id: FileID) -> String {
+    root + "/files/" + id.to_string().as_str()
+}
+
+fn load_rich_text(json: &JsonValue) -> RichText {
+    let marks = match &json["marks"] {
+        JsonValue::Array(marks) => marks.into_iter().filter_map(|json| {
+            let kind = match json["type"].as_str()? {
+                "bold" => MarkKind::Bold,
+                "italic" => MarkKind::Italic,
+                "strikethrough" => MarkKind::Strikethrough,
+                "code" => MarkKind::Code,
+                "link" => MarkKind::Link(json["href"].as_str()?.to_string()),
+                "file-link" => MarkKind::FileLink(json["file"].as_str()?.parse().ok()?),
+                _ => return None,
+            };
+            Some(Mark {
+                kind,
+                start: json["start"].as_usize()?,
+                end: json["end"].as_usize()?,
+            })
+        }).collect(),
+        _ => vec![],
+    };
+    RichText { text: json["text"].to_string(), marks }
+}
+
+fn store_rich_text(t: &str, text: &RichText) -> JsonValue {
+    let mut json = object! { "type": t, text: text.text.to_string() };
+    if !text.marks.is_empty() {
+        json["marks"] = JsonValue::Array(text.marks.iter().map(|mark| {
+            let mut m = object! {
+                "type": match mark.kind {
+                    MarkKind::Bold => "bold",
+                    MarkKind::Italic => "italic",
+                    MarkKind::Strikethrough => "strikethrough",
+                    MarkKind::Code => "code",
+                    MarkKind::Link(_) => "link",
+                    MarkKind::FileLink(_) => "file-link",
+                },
+                start: mark.start,
+                end: mark.end,
+            };
+            match &mark.kind {
+                MarkKind::Link(href) => m["href"] = href.as_str().into(),
+                MarkKind::FileLink(id) => m["file"] = id.to_string().into(),
+                _ => (),
+            }
+            m
+        }).collect());
+    }
+    json
+}
+
 pub fn load_table_content(root: String, id: FileID) -> Option<TableContent> {
     let path = root + "/files/" + id.to_string().as_str() + "/content.json";
     let json = read_to_string(path).ok()?;
@@ -85,6 +213,12 @@ pub fn load_table_content(root: String, id: FileID) -> Option<TableContent> {
                 id: json["id"].to_string().parse().unwrap(),
                 name: json["name"].as_str().unwrap_or_else(|| "").to_string(),
                 unique: json["unique"].as_bool().unwrap_or(false),
+                kind: json["type"].as_str()
+                    .and_then(|kind| ColumnKind::from_name(kind, json["options"].members()
+                        .filter_map(|o| o.as_str().map(|o| o.to_string()))
+                        .collect(), json["table"].as_str().and_then(|t| t.parse().ok())))
+                    .unwrap_or_default(),
+                aggregate: json["aggregate"].as_str().and_then(Aggregate::from_name),
             }).collect(),
         _ => vec![],
     };
@@ -95,7 +229,31 @@ pub fn load_table_content(root: String, id: FileID) -> Option<TableContent> {
             }).collect(),
         _ => vec![],
     };
-    Some(TableContent { columns, cells })
+    let sort = json["sort"].members().filter_map(|json| Some(SortKey {
+        column: json["column"].as_str()?.parse().ok()?,
+        descending: json["descending"].as_bool().unwrap_or(false),
+    })).collect();
+    let filters = json["filters"].members().filter_map(|json| Some(Filter {
+        column: json["column"].as_str()?.parse().ok()?,
+        rule: FilterRule::from_name(json["rule"].as_str()?, json["value"].as_str().unwrap_or("").to_string())?,
+    })).collect();
+    let rows = json["rows"].members().filter_map(|id| id.as_str()?.parse().ok()).collect();
+    let layout = json["layout"].as_str().and_then(Layout::from_name).unwrap_or_default();
+    let board_column = json["board_column"].as_str().and_then(|id| id.parse().ok());
+    let calendar_column = json["calendar_column"].as_str().and_then(|id| id.parse().ok());
+    let charts = json["charts"].members().filter_map(|json| Some(Chart {
+        kind: ChartKind::from_name(json["kind"].as_str()?)?,
+        labels: json["labels"].as_str()?.parse().ok()?,
+        values: json["values"].as_str()?.parse().ok()?,
+    })).collect();
+    let pivot = Some(&json["pivot"]).filter(|json| json.is_object()).and_then(|json| Some(Pivot {
+        group: json["group"].as_str()?.parse().ok()?,
+        value: json["value"].as_str()?.parse().ok()?,
+        aggregate: Aggregate::from_name(json["aggregate"].as_str()?)?,
+    }));
+    let mut content = TableContent { columns, cells, rows, sort, filters, layout, board_column, calendar_column, charts, pivot };
+    content.fill_row_ids();
+    Some(content)
 }
 
 pub fn store_table_content(root: String, id: FileID, table: &TableContent) {
@@ -106,8 +264,35 @@ pub fn store_table_content(root: String, id: FileID, table: &TableContent) {
             id: column.id.to_string(),
             name: column.name.clone(),
             unique: column.unique,
+            "type": column.kind.name(),
+            options: column.kind.options().to_vec(),
+            table: column.kind.table().map(|t| t.to_string()),
+            aggregate: column.aggregate.map(|a| a.name()),
         }).collect::<Vec<_>>(),
         cells: table.cells.iter().map(|cell| cell.content.clone()).collect::<Vec<_>>(),
+        rows: table.rows.iter().map(|id| id.to_string()).collect::<Vec<_>>(),
+        sort: table.sort.iter().map(|key| object! {
+            column: key.column.to_string(),
+            descending: key.descending,
+        }).collect::<Vec<_>>(),
+        filters: table.filters.iter().map(|filter| object! {
+            column: filter.column.to_string(),
+            rule: filter.rule.name(),
+            value: filter.rule.value(),
+        }).collect::<Vec<_>>(),
+        layout: table.layout.name(),
+        board_column: table.board_column.map(|id| id.to_string()),
+        calendar_column: table.calendar_column.map(|id| id.to_string()),
+        charts: table.charts.iter().map(|chart| object! {
+            kind: chart.kind.name(),
+            labels: chart.labels.to_string(),
+            values: chart.values.to_string(),
+        }).collect::<Vec<_>>(),
+        pivot: table.pivot.as_ref().map(|pivot| object! {
+            group: pivot.group.to_string(),
+            value: pivot.value.to_string(),
+            aggregate: pivot.aggregate.name(),
+        }),
     };
     let _ = std::fs::write(dir + "/content.json", json.to_string());
 }
\ No newline at end of file
@@ -16,7 +16,7 @@ pub struct Command {
 }
 
 impl Command {
-    pub fn all() -> [Self; 4] {[
+    pub fn all() -> [Self; 10] {[
         Self {
             label: "About Papier".to_string(),
             keywords: vec!["about".to_string(), "papier".to_string()],
@@ -38,6 +38,48 @@ impl Command {
             action_name: "win.new-table",
             param: None,
         },
+        Self {
+            label: "Show Open To-Dos".to_string(),
+            keywords: vec!["todo".to_string(), "check".to_string(), "task".to_string(), "open".to_string()],
+            icon_name: "object-select-symbolic",
+            action_name: "win.todos",
+            param: None,
+        },
+        Self {
+            label: "Export as Markdown".to_string(),
+            keywords: vec!["export".to_string(), "markdown".to_string(), "md".to_string(), "save".to_string()],
+            icon_name: "document-save-as-symbolic",
+            action_name: "win.export-markdown",
+            param: None,
+        },
+        Self {
+            label: "Import Markdown".to_string(),
+            keywords: vec!["import".to_string(), "markdown".to_string(), "md".to_string(), "open".to_string()],
+            icon_name: "document-open-symbolic",
+            action_name: "win.import-markdown",
+            param: None,
+        },
+        Self {
+            label: "Export as CSV".to_string(),
+            keywords: vec!["export".to_string(), "csv".to_string(), "table".to_string(), "save".to_string()],
+            icon_name: "document-save-as-symbolic",
+            action_name: "win.export-table",
+            param: Some("csv".to_variant()),
+        },
+        Self {
+            label: "Export as TSV".to_string(),
+            keywords: vec!["export".to_string(), "tsv".to_string(), "table".to_string(), "save".to_string()],
+            icon_name: "document-save-as-symbolic",
+            action_name: "win.export-table",
+            param: Some("tsv".to_variant()),
+        },
+        Self {
+            label: "Import CSV or TSV".to_string(),
+            keywords: vec!["import".to_string(), "csv".to_string(), "tsv".to_string(), "table".to_string()],
+            icon_name: "document-open-symbolic",
+            action_name: "win.import-table",
+            param: None,
+        },
         Self {
             label: "Show Keyboard Shortcuts".to_string(),
             keywords: vec!["help".to_string(), "accelerator".to_string(), "shortcut".to_string(), "keyboard".to_string()],
@@ -59,4 +101,14 @@ impl Command {
             param: Some(id.to_string().to_variant()),
         }
     }
+
+    fn open_todo(id: FileID, text: &str, title: &str) -> Self {
+        Command {
+            label: text.to_string() + " — " + title,
+            keywords: vec![],
+            icon_name: "object-select-symbolic",
+            action_name: "win.open-file",
+            param: Some(id.to_string().to_variant()),
+        }
+    }
 }
\ No newline at end of file
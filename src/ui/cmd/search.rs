@@ -1,6 +1,7 @@
 use std::rc::Rc;
 use std::sync::Mutex;
 
+use crate::data::FileID;
 use crate::db::DB;
 use adw::{Window};
 use glib::{BoxedAnyObject, clone, Object};
@@ -15,19 +16,12 @@ use super::Command;
 
 impl Command {
     pub fn search(db: &DB, q: &str) -> ListStore {
-        let ids = db.ids().into_iter();
         if q.is_empty() {
-            let model = ListStore::new(BoxedAnyObject::static_type());
-            for id in ids {
-                let display = db.get_file(id).unwrap();
-                model.append(&BoxedAnyObject::new(
-                    Self::open_file(id, &display.title, display.category)));
-            }
-            return model;
+            return Self::files(db, q);
         }
         let q = q.trim().to_lowercase();
     
-        let mut commands = {
+        let commands = {
             Self::all().iter()
                 .filter_map(|action| {
                     let m: f32 = action.keywords.iter().map(|k| fuzzy_compare(&q, &k)).sum();
@@ -37,32 +31,91 @@ impl Command {
                         None
                     }
                 })
-                .chain(ids.filter_map(|id| {
-                    let display = db.get_file(id).unwrap();
-                    let m = fuzzy_compare(&q, &display.title.trim().to_lowercase());
-                    let mk: f32 = display.keywords.iter().map(|k| fuzzy_compare(&q, &k.trim().to_lowercase())).sum();
-                    let m = m + mk;
-                    if m > 0.4 {
-                        Some((Self::open_file(id, &display.title, display.category), m))
-                    } else {
-                        None
-                    }
-                }))
+                .chain(Self::file_matches(db, &q))
                 .collect::<Vec<_>>()
         };
-    
+        Self::sorted_model(commands)
+    }
+
+    pub fn files(db: &DB, q: &str) -> ListStore {
+        if q.is_empty() {
+            let model = ListStore::new(BoxedAnyObject::static_type());
+            for id in db.ids() {
+                let display = db.get_file(id).unwrap();
+                model.append(&BoxedAnyObject::new(
+                    Self::open_file(id, &display.title, display.category)));
+            }
+            return model;
+        }
+        let q = q.trim().to_lowercase();
+        Self::sorted_model(Self::file_matches(db, &q).collect())
+    }
+
+    fn file_matches<'a>(db: &'a DB, q: &'a str) -> impl Iterator<Item = (Self, f32)> + 'a {
+        db.ids().into_iter().filter_map(move |id| {
+            let display = db.get_file(id).unwrap();
+            let m = fuzzy_compare(q, &display.title.trim().to_lowercase());
+            let mk: f32 = display.keywords.iter().map(|k| fuzzy_compare(q, &k.trim().to_lowercase())).sum();
+            let m = m + mk;
+            if m > 0.4 {
+                Some((Self::open_file(id, &display.title, display.category), m))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn sorted_model(mut commands: Vec<(Self, f32)>) -> ListStore {
         commands.sort_by(|(_, a), (_, b)| a.total_cmp(b));
-        let commands = commands.into_iter().map(|(c, _)| c).collect::<Vec<_>>();
-    
         let model = ListStore::new(BoxedAnyObject::static_type());
-        for command in commands {
+        for (command, _) in commands {
             model.append(&BoxedAnyObject::new(command));
         }
         model
     }
+
+    pub fn todos(db: &DB, q: &str) -> ListStore {
+        let q = q.trim().to_lowercase();
+        let model = ListStore::new(BoxedAnyObject::static_type());
+        for (id, text) in db.open_todos() {
+            if q.is_empty() || fuzzy_compare(&q, &text.trim().to_lowercase()) > 0.4 {
+                let title = &db.get_file(id).unwrap().title;
+                model.append(&BoxedAnyObject::new(Self::open_todo(id, &text, title)));
+            }
+        }
+        model
+    }
 }
 
 pub fn command_search_window(db: &Rc<Mutex<DB>>, app: &adw::Application, close_win_on_esc: bool) {
+    command_window(db, app, "Search files & actions…", close_win_on_esc, Command::search, run_command)
+}
+
+pub fn todo_search_window(db: &Rc<Mutex<DB>>, app: &adw::Application) {
+    command_window(db, app, "Search open to-dos…", false, Command::todos, run_command)
+}
+
+/// Lets the user pick a file, without opening it
+pub fn file_picker_window<F: Fn(FileID) + 'static>(db: &Rc<Mutex<DB>>, app: &adw::Application, on_pick: F) {
+    command_window(db, app, "Link to file…", false, Command::files, move |_, command| {
+        let id = command.param.unwrap().str().unwrap().parse::<FileID>().unwrap();
+        on_pick(id)
+    })
+}
+
+fn run_command(app: &adw::Application, command: Command) {
+    app.active_window().expect("No active window")
+        .activate_action(command.action_name, command.param.as_ref()).unwrap();
+}
+
+fn command_window<F: Fn(&adw::Application, Command) + 'static>(
+    db: &Rc<Mutex<DB>>,
+    app: &adw::Application,
+    placeholder: &str,
+    close_win_on_esc: bool,
+    search: fn(&DB, &str) -> ListStore,
+    run: F,
+) {
     let factory = SignalListItemFactory::new();
     factory.connect_setup(create_cmd_item_view);
     factory.connect_bind(clone!(@strong db => move |_, item| {
@@ -81,7 +134,7 @@ pub fn command_search_window(db: &Rc<Mutex<DB>>, app: &adw::Application, close_w
     }));
 
     let model = SingleSelection::builder()
-        .model(&Command::search(&db.lock().unwrap(), ""))
+        .model(&search(&db.lock().unwrap(), ""))
         .can_unselect(true)
         .build();
     model.unselect_item(0);
@@ -94,12 +147,12 @@ pub fn command_search_window(db: &Rc<Mutex<DB>>, app: &adw::Application, close_w
         .build();
 
     let search_bar = SearchEntry::builder()
-        .placeholder_text("Search files & actions…")
+        .placeholder_text(placeholder)
         .hexpand(true)
         .build();
 
     search_bar.connect_text_notify(clone!(@strong model, @strong db => move |sb| {
-        model.set_model(Some(&Command::search(&db.lock().unwrap(), &sb.text())));
+        model.set_model(Some(&search(&db.lock().unwrap(), &sb.text())));
     }));
 
     let scroll = ScrolledWindow::builder()
@@ -154,8 +207,7 @@ pub fn command_search_window(db: &Rc<Mutex<DB>>, app: &adw::Application, close_w
             .and_downcast::<BoxedAnyObject>().unwrap()
             .borrow_mut::<Command>().clone();
         window.close();
-        app.active_window().expect("No active window")
-            .activate_action(command.action_name, command.param.as_ref()).unwrap();
+        run(&app, command);
     }));
 
     window.present();
@@ -1,7 +1,8 @@
 use std::{rc::Rc, sync::Mutex};
 
 use glib::clone;
-use gtk4::{Orientation, FlowBox, traits::{BoxExt, WidgetExt, EditableExt, ButtonExt, EntryExt, PopoverExt}, Align, SelectionMode, Label, Text, Button, Popover, Entry};
+use glib::ToVariant;
+use gtk4::{Orientation, FlowBox, traits::{BoxExt, WidgetExt, EditableExt, ButtonExt, EntryExt, PopoverExt, ActionableExt}, Align, SelectionMode, Label, Text, Button, Popover, Entry};
 
 use crate::{db::DB, data::FileID};
 
@@ -69,9 +70,48 @@ pub(super) fn create_header<F: Fn(String) + 'static>(
     }));
     keywords_box.append(&add_button);
     header.append(&keywords_box);
+
+    let backlinks_box = FlowBox::builder()
+        .css_classes(["backlinks"])
+        .orientation(Orientation::Horizontal)
+        .halign(Align::Start)
+        .selection_mode(SelectionMode::None)
+        .visible(false)
+        .build();
+    header.append(&backlinks_box);
+    update_backlinks(db, id, &backlinks_box);
+    // Other files may have been linking here since we last looked, but whatever maps the header
+    // may be holding the DB, so it's looked up once that's done
+    header.connect_map(clone!(@strong db, @strong backlinks_box => move |_| {
+        glib::idle_add_local_once(clone!(@strong db, @strong backlinks_box => move || {
+            update_backlinks(&db, id, &backlinks_box);
+        }));
+    }));
     header
 }
 
+fn update_backlinks(db: &Rc<Mutex<DB>>, id: FileID, backlinks_box: &FlowBox) {
+    while let Some(child) = backlinks_box.first_child() {
+        backlinks_box.remove(&child);
+    }
+    let db = db.lock().unwrap();
+    let backlinks = db.backlinks(id);
+    backlinks_box.set_visible(!backlinks.is_empty());
+    if backlinks.is_empty() {
+        return;
+    }
+    backlinks_box.append(&Label::new(Some("Linked from")));
+    for source in backlinks {
+        let button = Button::builder()
+            .css_classes(["flat"])
+            .label(&db.get_file(source).unwrap().title)
+            .action_name("win.open-file")
+            .action_target(&source.to_string().to_variant())
+            .build();
+        backlinks_box.append(&button);
+    }
+}
+
 fn create_keyword_chip(db: &Rc<Mutex<DB>>, id: FileID, keywords_box: &FlowBox, k: String) -> gtk4::Box {
     let keyword_chip = gtk4::Box::builder()
         .css_classes(["keyword-chip"])
@@ -16,10 +16,23 @@ mod text;
 
 pub fn display_file<F>(db: &Rc<Mutex<DB>>, id: FileID, on_rename: F) -> (Widget, Box<dyn Saveable>)
 where F: Fn(String) + 'static {
-    let borrowed_db = db.lock().unwrap();
-    let file = borrowed_db.get_file(id).unwrap();
-    match file.category {
-        Category::Text => display_text(db, id, &file.title, file.keywords.clone(), borrowed_db.get_text_content(id), on_rename),
-        Category::Table => display_table(db, id, &file.title, file.keywords.clone(), borrowed_db.get_table_content(id), on_rename),
+    // The DB isn't kept locked while the widgets are made, their handlers lock it themselves
+    let (category, title, keywords) = {
+        let db = db.lock().unwrap();
+        let file = db.get_file(id).unwrap();
+        (file.category, file.title.clone(), file.keywords.clone())
+    };
+    match category {
+        Category::Text => {
+            let (content, dir) = {
+                let db = db.lock().unwrap();
+                (db.get_text_content(id), db.file_dir(id))
+            };
+            display_text(db, id, dir, &title, keywords, content, on_rename)
+        },
+        Category::Table => {
+            let content = db.lock().unwrap().get_table_content(id);
+            display_table(db, id, &title, keywords, content, on_rename)
+        },
     }
 }
\ No newline at end of file
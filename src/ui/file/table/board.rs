@@ -0,0 +1,180 @@
+use adw::StatusPage;
+use glib::{clone, BoxedAnyObject};
+use gtk4::{prelude::*, gdk::{ContentProvider, DragAction}, Align, DragSource, GestureClick, DropDown, DropTarget, Label, Orientation, PolicyType, ScrolledWindow, WidgetPaintable};
+
+use crate::data::table::{Layout, column::ColumnKind, row::Row};
+
+use super::TableEditingState;
+use super::form::show_form;
+use super::rows::rows_changed;
+
+/// How many other fields a card shows under its title
+const CARD_FIELDS: usize = 3;
+
+/// The page of the board, filled by [`update_board`] while it's shown
+pub(super) fn create_board() -> gtk4::Box {
+    gtk4::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(12)
+        .build()
+}
+
+/// Recreates the lanes of the board from the shown rows, when it's the current layout
+pub(super) fn update_board(state: &TableEditingState) {
+    if state.layout.visible_child_name().as_deref() != Some(Layout::Board.name()) {
+        return;
+    }
+    while let Some(child) = state.board.first_child() {
+        state.board.remove(&child);
+    }
+    let columns = state.columns.borrow().clone();
+    let selects = columns.iter()
+        .enumerate()
+        .filter(|(_, c)| matches!(c.kind, ColumnKind::Select(_)))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    if selects.is_empty() {
+        state.board.append(&StatusPage::builder()
+            .icon_name("view-grid-symbolic")
+            .title("No Single Select Columns")
+            .description("Cards are grouped by the options of a single select column")
+            .vexpand(true)
+            .build());
+        return;
+    }
+    let chosen = selects.iter()
+        .position(|&i| Some(columns[i].id) == state.board_column.get())
+        .unwrap_or(0);
+    let i = selects[chosen];
+    state.board_column.set(Some(columns[i].id));
+
+    let names = selects.iter().map(|&i| columns[i].name.as_str()).collect::<Vec<_>>();
+    let group_by = DropDown::from_strings(&names);
+    group_by.set_selected(chosen as u32);
+    group_by.connect_selected_notify(clone!(@strong state, @strong columns, @strong selects => move |group_by| {
+        if let Some(&i) = selects.get(group_by.selected() as usize) {
+            state.board_column.set(Some(columns[i].id));
+        }
+        // Not while the drop down is still handling its own change
+        glib::idle_add_local_once(clone!(@strong state => move || update_board(&state)));
+    }));
+    let bar = gtk4::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .build();
+    bar.append(&Label::new(Some("Group By")));
+    bar.append(&group_by);
+    state.board.append(&bar);
+
+    let options = columns[i].kind.options().to_vec();
+    let lanes = std::iter::once(None).chain(options.iter().cloned().map(Some))
+        .map(|option| lane(state, i, option))
+        .collect::<Vec<_>>();
+    let model = state.model.borrow();
+    for row in (0..model.n_items()).filter_map(|n| model.item(n).and_downcast::<BoxedAnyObject>()) {
+        let value = row.borrow::<Row>().cells[i].content.clone();
+        // Anything that isn't one of the options goes with the empty cells
+        let lane = value.and_then(|v| options.iter().position(|o| *o == v)).map_or(0, |n| n + 1);
+        lanes[lane].append(&card(state, &row, i));
+    }
+
+    let board = gtk4::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(12)
+        .build();
+    for lane in &lanes {
+        board.append(lane);
+    }
+    state.board.append(&ScrolledWindow::builder()
+        .child(&board)
+        .vscrollbar_policy(PolicyType::Never)
+        .vexpand(true)
+        .build());
+}
+
+/// The lane of the rows whose `i`th cell is `option`, which cards can be dropped on to move them there
+fn lane(state: &TableEditingState, i: usize, option: Option<String>) -> gtk4::Box {
+    let lane = gtk4::Box::builder()
+        .css_classes(["board-lane"])
+        .orientation(Orientation::Vertical)
+        .spacing(6)
+        .build();
+    lane.append(&Label::builder()
+        .css_classes(["heading"])
+        .label(option.as_deref().unwrap_or("No Value"))
+        .halign(Align::Start)
+        .build());
+    let target = DropTarget::new(String::static_type(), DragAction::MOVE);
+    target.connect_drop(clone!(@strong state => move |_, value, _, _| {
+        match value.get::<String>() {
+            Ok(id) => {
+                move_card(&state, &id, i, option.clone());
+                true
+            },
+            Err(_) => false,
+        }
+    }));
+    lane.add_controller(target);
+    lane
+}
+
+/// The card of a row, titled by its first cell and showing the next few that aren't empty, which opens it as a form
+fn card(state: &TableEditingState, row: &BoxedAnyObject, i: usize) -> gtk4::Box {
+    let columns = state.columns.borrow();
+    let card = gtk4::Box::builder()
+        .css_classes(["card", "board-card"])
+        .orientation(Orientation::Vertical)
+        .spacing(3)
+        .build();
+    let title = state.shown_value(row, 0, columns[0].id).unwrap_or_default();
+    card.append(&Label::builder()
+        .css_classes(if title.is_empty() { ["dim-label"] } else { ["title"] })
+        .label(if title.is_empty() { "Untitled" } else { title.as_str() })
+        .halign(Align::Start)
+        .wrap(true)
+        .xalign(0.0)
+        .build());
+    let fields = columns.iter().enumerate()
+        .skip(1)
+        .filter(|&(n, _)| n != i)
+        .filter_map(|(n, column)| Some((column, state.shown_value(row, n, column.id).filter(|v| !v.is_empty())?)))
+        .take(CARD_FIELDS);
+    for (column, value) in fields {
+        card.append(&Label::builder()
+            .css_classes(["caption"])
+            .label(format!("{}: {value}", column.name))
+            .halign(Align::Start)
+            .wrap(true)
+            .xalign(0.0)
+            .build());
+    }
+
+    let id = row.borrow::<Row>().id.to_string();
+    let source = DragSource::new();
+    source.set_actions(DragAction::MOVE);
+    source.connect_prepare(move |_, _, _| Some(ContentProvider::for_value(&id.to_value())));
+    source.connect_drag_begin(clone!(@strong card => move |source, _| {
+        source.set_icon(Some(&WidgetPaintable::new(Some(&card))), 0, 0);
+    }));
+    card.add_controller(source);
+    let click = GestureClick::new();
+    click.connect_released(clone!(@strong state, @strong row => move |_, _, _, _| show_form(&state, Some(row.clone()))));
+    card.add_controller(click);
+    card
+}
+
+/// Sets the `i`th cell of the row with the ID `id` to `option`
+fn move_card(state: &TableEditingState, id: &str, i: usize, option: Option<String>) {
+    let position = state.rows.iter::<BoxedAnyObject>()
+        .filter_map(Result::ok)
+        .position(|row| row.borrow::<Row>().id.to_string() == id);
+    if let Some(position) = position {
+        let row = state.rows.item(position as u32).and_downcast::<BoxedAnyObject>().unwrap();
+        row.borrow_mut::<Row>().cells[i].content = option;
+        // Rebinds the row's cells in the grid
+        state.rows.items_changed(position as u32, 1, 1);
+        rows_changed(state);
+    }
+    // The lanes can't be replaced while one of them is handling the drop
+    glib::idle_add_local_once(clone!(@strong state => move || update_board(&state)));
+}
@@ -0,0 +1,181 @@
+use adw::StatusPage;
+use glib::{clone, BoxedAnyObject, DateTime};
+use gtk4::{prelude::*, pango::EllipsizeMode, Align, Button, DropDown, GestureClick, Grid, Label, Orientation};
+
+use crate::data::table::{Layout, column::{parse_date, ColumnKind}, row::Row};
+
+use super::TableEditingState;
+use super::form::show_form;
+use super::rows::rows_changed;
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// The page of the calendar, filled by [`update_calendar`] while it's shown
+pub(super) fn create_calendar() -> gtk4::Box {
+    gtk4::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(12)
+        .build()
+}
+
+/// The year and month of today
+pub(super) fn this_month() -> (i32, u32) {
+    let now = DateTime::now_local().unwrap();
+    (now.year(), now.month() as u32)
+}
+
+/// Recreates the month of the calendar from the shown rows, when it's the current layout
+pub(super) fn update_calendar(state: &TableEditingState) {
+    if state.layout.visible_child_name().as_deref() != Some(Layout::Calendar.name()) {
+        return;
+    }
+    while let Some(child) = state.calendar.first_child() {
+        state.calendar.remove(&child);
+    }
+    let columns = state.columns.borrow().clone();
+    let dates = columns.iter()
+        .enumerate()
+        .filter(|(_, c)| matches!(c.kind, ColumnKind::Date))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    if dates.is_empty() {
+        state.calendar.append(&StatusPage::builder()
+            .icon_name("x-office-calendar-symbolic")
+            .title("No Date Columns")
+            .description("Rows are placed on the days of a date column")
+            .vexpand(true)
+            .build());
+        return;
+    }
+    let chosen = dates.iter()
+        .position(|&i| Some(columns[i].id) == state.calendar_column.get())
+        .unwrap_or(0);
+    let i = dates[chosen];
+    state.calendar_column.set(Some(columns[i].id));
+    let (year, month) = state.month.get();
+    let first = DateTime::from_local(year, month as i32, 1, 0, 0, 0.0).unwrap();
+
+    let names = dates.iter().map(|&i| columns[i].name.as_str()).collect::<Vec<_>>();
+    let date_column = DropDown::from_strings(&names);
+    date_column.set_selected(chosen as u32);
+    date_column.connect_selected_notify(clone!(@strong state, @strong columns, @strong dates => move |date_column| {
+        if let Some(&i) = dates.get(date_column.selected() as usize) {
+            state.calendar_column.set(Some(columns[i].id));
+        }
+        // Not while the drop down is still handling its own change
+        glib::idle_add_local_once(clone!(@strong state => move || update_calendar(&state)));
+    }));
+    let bar = gtk4::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .build();
+    bar.append(&Label::new(Some("Date Column")));
+    bar.append(&date_column);
+    bar.append(&Label::builder()
+        .css_classes(["title-4"])
+        .label(first.format("%B %Y").unwrap().as_str())
+        .hexpand(true)
+        .build());
+    for (icon, tooltip, to) in [
+        ("go-previous-symbolic", "Previous Month", if month == 1 { (year - 1, 12) } else { (year, month - 1) }),
+        ("go-next-symbolic", "Next Month", if month == 12 { (year + 1, 1) } else { (year, month + 1) }),
+    ] {
+        let button = Button::builder()
+            .css_classes(["flat"])
+            .icon_name(icon)
+            .tooltip_text(tooltip)
+            .build();
+        button.connect_clicked(clone!(@strong state => move |_| show_month(&state, to)));
+        bar.append(&button);
+    }
+    let today = Button::builder()
+        .css_classes(["flat"])
+        .label("Today")
+        .build();
+    today.connect_clicked(clone!(@strong state => move |_| show_month(&state, this_month())));
+    bar.append(&today);
+    state.calendar.append(&bar);
+
+    let grid = Grid::builder()
+        .column_homogeneous(true)
+        .row_homogeneous(true)
+        .column_spacing(6)
+        .row_spacing(6)
+        .vexpand(true)
+        .build();
+    for (n, name) in WEEKDAYS.iter().enumerate() {
+        grid.attach(&Label::builder()
+            .css_classes(["dim-label"])
+            .label(*name)
+            .build(), n as i32, 0, 1, 1);
+    }
+    // Mondays are 1, so the weekday is also how many days of the week are before the first
+    let offset = first.day_of_week() - 1;
+    let days = first.add_months(1).and_then(|d| d.add_days(-1)).map_or(31, |d| d.day_of_month());
+    let today = DateTime::now_local().unwrap();
+    let days = (1..=days).map(|day| {
+        let cell = create_day(state, i, (year, month, day as u32));
+        if (today.year(), today.month(), today.day_of_month()) == (year, month as i32, day) {
+            cell.add_css_class("today");
+        }
+        let n = offset + day - 1;
+        grid.attach(&cell, n % 7, n / 7 + 1, 1, 1);
+        cell
+    }).collect::<Vec<_>>();
+
+    let model = state.model.borrow();
+    for row in (0..model.n_items()).filter_map(|n| model.item(n).and_downcast::<BoxedAnyObject>()) {
+        let date = row.borrow::<Row>().cells[i].content.as_deref().and_then(parse_date);
+        if let Some((_, _, d)) = date.filter(|&(y, m, _)| (y, m) == (year, month)) {
+            days[d as usize - 1].append(&entry(state, &row));
+        }
+    }
+    state.calendar.append(&grid);
+}
+
+fn show_month(state: &TableEditingState, month: (i32, u32)) {
+    state.month.set(month);
+    // Not while one of the buttons being replaced is handling its click
+    glib::idle_add_local_once(clone!(@strong state => move || update_calendar(&state)));
+}
+
+/// The day's box, to which the rows on it are added, and clicking which creates a row on that day
+fn create_day(state: &TableEditingState, i: usize, (year, month, day): (i32, u32, u32)) -> gtk4::Box {
+    let cell = gtk4::Box::builder()
+        .css_classes(["calendar-day"])
+        .orientation(Orientation::Vertical)
+        .spacing(3)
+        .build();
+    cell.append(&Label::builder()
+        .css_classes(["caption-heading"])
+        .label(day.to_string())
+        .halign(Align::Start)
+        .build());
+    let click = GestureClick::new();
+    click.connect_released(clone!(@strong state => move |_, _, _, _| {
+        let mut row = Row::create_empty(state.columns.borrow().len());
+        row.cells[i].content = Some(format!("{:04}-{:02}-{:02}", year, month, day));
+        state.rows.append(&BoxedAnyObject::new(row));
+        rows_changed(&state);
+        glib::idle_add_local_once(clone!(@strong state => move || update_calendar(&state)));
+    }));
+    cell.add_controller(click);
+    cell
+}
+
+/// A row on the calendar, labelled by its first cell, which opens it as a form
+fn entry(state: &TableEditingState, row: &BoxedAnyObject) -> Button {
+    let title = state.shown_value(row, 0, state.columns.borrow()[0].id).unwrap_or_default();
+    let label = Label::builder()
+        .label(if title.is_empty() { "Untitled" } else { title.as_str() })
+        .ellipsize(EllipsizeMode::End)
+        .xalign(0.0)
+        .build();
+    let entry = Button::builder()
+        .css_classes(["calendar-entry"])
+        .child(&label)
+        .tooltip_text(title.as_str())
+        .build();
+    entry.connect_clicked(clone!(@strong state, @strong row => move |_| show_form(&state, Some(row.clone()))));
+    entry
+}
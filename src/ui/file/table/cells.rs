@@ -0,0 +1,310 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use glib::{clone, DateTime, Object, SignalHandlerId};
+use gtk4::{prelude::*, gio::Cancellable, pango::EllipsizeMode, Align, Button, Calendar, CheckButton, DropDown, InputPurpose, Label, MenuButton, Orientation, Popover, PolicyType, ScrolledWindow, SearchEntry, Text, UriLauncher, Viewport, Widget, Window, INVALID_LIST_POSITION};
+
+use crate::data::table::column::{parse_date, ColumnKind};
+use crate::data::table::relation::linked_rows;
+use crate::data::table::row::RowID;
+
+use super::links::{relation_label, LinkTargets};
+use super::rows::row_menu;
+
+/// How many rows the relation picker lists at once, searching finds the others
+const PICKER_ROWS: usize = 50;
+
+/// Signal handlers of a bound cell, to disconnect when it's unbound
+pub(super) type CellHandlers = Vec<(Object, SignalHandlerId)>;
+
+/// Creates the widget for a cell of `kind`, to be filled by [`bind_cell`]
+pub(super) fn create_cell(kind: &ColumnKind) -> Widget {
+    match kind {
+        ColumnKind::Text => create_text().upcast(),
+        ColumnKind::Number => {
+            let text = create_text();
+            text.set_xalign(1.0);
+            text.set_input_purpose(InputPurpose::Number);
+            text.upcast()
+        },
+        ColumnKind::Date => {
+            let text = create_text();
+            text.set_placeholder_text(Some("YYYY-MM-DD"));
+            let calendar = Calendar::new();
+            let popover = Popover::builder()
+                .child(&calendar)
+                .build();
+            popover.connect_show(clone!(@strong text, @strong calendar => move |_| {
+                if let Some((y, m, d)) = parse_date(text.text().trim()) {
+                    if let Ok(date) = DateTime::from_local(y, m as i32, d as i32, 0, 0, 0.0) {
+                        calendar.select_day(&date);
+                    }
+                }
+            }));
+            calendar.connect_day_selected(clone!(@strong text => move |calendar| {
+                let date = calendar.date();
+                let date = format!("{:04}-{:02}-{:02}", date.year(), date.month(), date.day_of_month());
+                if text.text() != date {
+                    text.set_text(&date);
+                }
+            }));
+            let button = MenuButton::builder()
+                .css_classes(["flat"])
+                .icon_name("x-office-calendar-symbolic")
+                .popover(&popover)
+                .build();
+            cell_row(&text, &button).upcast()
+        },
+        ColumnKind::Url => {
+            let text = create_text();
+            text.set_input_purpose(InputPurpose::Url);
+            let button = Button::builder()
+                .css_classes(["flat"])
+                .icon_name("web-browser-symbolic")
+                .tooltip_text("Open Link")
+                .build();
+            button.connect_clicked(clone!(@strong text => move |button| {
+                if let Some(url) = ColumnKind::Url.check(&text.text()) {
+                    let window = button.root().and_downcast::<Window>();
+                    UriLauncher::new(&url).launch(window.as_ref(), None::<&Cancellable>, |_| ());
+                }
+            }));
+            cell_row(&text, &button).upcast()
+        },
+        ColumnKind::Boolean => CheckButton::builder()
+            .halign(Align::Center)
+            .build()
+            .upcast(),
+        ColumnKind::Select(options) => {
+            let mut strings = vec![""];
+            strings.extend(options.iter().map(|o| o.as_str()));
+            DropDown::from_strings(&strings).upcast()
+        },
+        ColumnKind::MultiSelect(options) => {
+            let checks = gtk4::Box::builder()
+                .orientation(Orientation::Vertical)
+                .build();
+            for option in options {
+                checks.append(&CheckButton::with_label(option));
+            }
+            MenuButton::builder()
+                .css_classes(["flat"])
+                .popover(&Popover::builder().child(&checks).build())
+                .build()
+                .upcast()
+        },
+        ColumnKind::Relation(_) => {
+            let picker = gtk4::Box::builder()
+                .orientation(Orientation::Vertical)
+                .spacing(6)
+                .build();
+            picker.append(&SearchEntry::new());
+            picker.append(&ScrolledWindow::builder()
+                .hscrollbar_policy(PolicyType::Never)
+                .max_content_height(300)
+                .propagate_natural_height(true)
+                .child(&gtk4::Box::builder().orientation(Orientation::Vertical).build())
+                .build());
+            MenuButton::builder()
+                .css_classes(["flat"])
+                .popover(&Popover::builder().child(&picker).build())
+                .build()
+                .upcast()
+        },
+        ColumnKind::Rollup(_) => Label::builder()
+            .xalign(0.0)
+            .ellipsize(EllipsizeMode::End)
+            .build()
+            .upcast(),
+    }
+}
+
+/// Shows `content` in a widget made by [`create_cell`], calling `on_change` whenever a valid value is entered.
+/// Relation cells pick from `targets`, and rollup ones show `content` as it is.
+pub(super) fn bind_cell<F: Fn(Option<String>) + 'static>(cell: &Widget, kind: &ColumnKind, content: Option<&str>, targets: LinkTargets, on_change: F) -> CellHandlers {
+    match kind {
+        ColumnKind::Text => {
+            let text = cell.downcast_ref::<Text>().unwrap();
+            text.set_text(content.unwrap_or(""));
+            let handler = text.connect_text_notify(move |text| {
+                on_change(Some(text.text().to_string()));
+            });
+            vec![(text.clone().upcast(), handler)]
+        },
+        ColumnKind::Number => bind_checked_text(cell.downcast_ref::<Text>().unwrap(), kind, content, on_change),
+        ColumnKind::Date | ColumnKind::Url => {
+            let text = cell.first_child().and_downcast::<Text>().unwrap();
+            bind_checked_text(&text, kind, content, on_change)
+        },
+        ColumnKind::Boolean => {
+            let check = cell.downcast_ref::<CheckButton>().unwrap();
+            check.set_active(content.and_then(|c| kind.check(c)).as_deref() == Some("true"));
+            let handler = check.connect_toggled(move |check| {
+                on_change(Some(check.is_active().to_string()));
+            });
+            vec![(check.clone().upcast(), handler)]
+        },
+        ColumnKind::Select(options) => {
+            let dropdown = cell.downcast_ref::<DropDown>().unwrap();
+            let selected = content
+                .and_then(|c| options.iter().position(|o| o == c))
+                .map_or(0, |i| i + 1);
+            dropdown.set_selected(selected as u32);
+            let options = options.clone();
+            let handler = dropdown.connect_selected_notify(move |dropdown| {
+                on_change(match dropdown.selected() {
+                    0 | INVALID_LIST_POSITION => None,
+                    i => options.get(i as usize - 1).cloned(),
+                });
+            });
+            vec![(dropdown.clone().upcast(), handler)]
+        },
+        ColumnKind::MultiSelect(options) => {
+            let button = cell.downcast_ref::<MenuButton>().unwrap();
+            let checks = multi_select_checks(button);
+            let chosen = content.unwrap_or("").lines().collect::<Vec<_>>();
+            for (check, option) in checks.iter().zip(options) {
+                check.set_active(chosen.contains(&option.as_str()));
+            }
+            button.set_label(&chosen.join(", "));
+            let on_change = Rc::new(on_change);
+            checks.iter().map(|check| {
+                let handler = check.connect_toggled(clone!(@strong button, @strong checks, @strong options, @strong on_change => move |_| {
+                    let chosen = checks.iter().zip(&options)
+                        .filter(|(check, _)| check.is_active())
+                        .map(|(_, option)| option.as_str())
+                        .collect::<Vec<_>>();
+                    button.set_label(&chosen.join(", "));
+                    on_change(if chosen.is_empty() { None } else { Some(chosen.join("\n")) });
+                }));
+                (check.clone().upcast(), handler)
+            }).collect()
+        },
+        ColumnKind::Relation(_) => bind_relation(cell.downcast_ref::<MenuButton>().unwrap(), content, targets, on_change),
+        ColumnKind::Rollup(_) => {
+            cell.downcast_ref::<Label>().unwrap().set_text(content.unwrap_or(""));
+            vec![]
+        },
+    }
+}
+
+/// Lists the target rows matching the search whenever the picker opens, linking the checked ones
+fn bind_relation<F: Fn(Option<String>) + 'static>(button: &MenuButton, content: Option<&str>, targets: LinkTargets, on_change: F) -> CellHandlers {
+    button.set_label(&relation_label(&targets, content.unwrap_or("")));
+    let popover = button.popover().unwrap();
+    let search = popover.child().and_then(|p| p.first_child()).and_downcast::<SearchEntry>().unwrap();
+    let list = search.next_sibling()
+        .and_downcast::<ScrolledWindow>()
+        .and_then(|s| s.child())
+        .and_then(|c| match c.downcast::<Viewport>() {
+            Ok(viewport) => viewport.child(),
+            Err(c) => Some(c),
+        })
+        .and_downcast::<gtk4::Box>()
+        .unwrap();
+    let chosen = Rc::new(RefCell::new(linked_rows(content.unwrap_or(""))));
+    let on_change = Rc::new(on_change);
+    let fill = Rc::new(clone!(@strong button, @strong search, @strong list, @strong chosen, @strong targets, @strong on_change => move || {
+        while let Some(child) = list.first_child() {
+            list.remove(&child);
+        }
+        let query = search.text().to_lowercase();
+        let matching = targets.iter().filter(|(_, label)| label.to_lowercase().contains(&query));
+        for &(id, ref label) in matching.take(PICKER_ROWS) {
+            let check = CheckButton::with_label(if label.is_empty() { "Untitled" } else { label });
+            check.set_active(chosen.borrow().contains(&id));
+            check.connect_toggled(clone!(@strong button, @strong chosen, @strong targets, @strong on_change => move |check| {
+                let mut rows = chosen.borrow_mut();
+                if !check.is_active() {
+                    rows.retain(|row| *row != id);
+                } else if !rows.contains(&id) {
+                    rows.push(id);
+                }
+                let content = rows.iter().map(RowID::to_string).collect::<Vec<_>>().join("\n");
+                drop(rows);
+                button.set_label(&relation_label(&targets, &content));
+                on_change(Some(content).filter(|c| !c.is_empty()));
+            }));
+            list.append(&check);
+        }
+    }));
+    let shown = popover.connect_show(clone!(@strong fill => move |_| fill()));
+    let searched = search.connect_search_changed(move |_| fill());
+    vec![(popover.upcast(), shown), (search.upcast(), searched)]
+}
+
+/// Shows `shown` in a text or number cell without it counting as an edit, like a formula's value
+pub(super) fn show_in_cell(cell: &Widget, shown: &str) {
+    let text = match cell.downcast_ref::<Text>() {
+        Some(text) => text,
+        None => return,
+    };
+    let handlers = unsafe {
+        cell.data::<CellHandlers>("cell-signals").map(|h| h.as_ref())
+    };
+    for (object, handler_id) in handlers.into_iter().flatten() {
+        object.block_signal(handler_id);
+    }
+    text.set_text(shown);
+    for (object, handler_id) in handlers.into_iter().flatten() {
+        object.unblock_signal(handler_id);
+    }
+}
+
+/// The text a cell is edited in, for the kinds that have one
+pub(super) fn cell_text(cell: &Widget) -> Option<Text> {
+    cell.clone().downcast::<Text>().ok()
+        .or_else(|| cell.first_child().and_downcast::<Text>())
+}
+
+fn create_text() -> Text {
+    Text::builder()
+        .editable(true)
+        .css_name("cell")
+        .hexpand(true)
+        .extra_menu(&row_menu())
+        .build()
+}
+
+fn cell_row(text: &Text, button: &impl IsA<Widget>) -> gtk4::Box {
+    let row = gtk4::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .build();
+    row.append(text);
+    row.append(button);
+    row
+}
+
+fn multi_select_checks(button: &MenuButton) -> Vec<CheckButton> {
+    let mut checks = Vec::new();
+    let mut child = button.popover().and_then(|p| p.child()).and_then(|c| c.first_child());
+    while let Some(check) = child {
+        child = check.next_sibling();
+        checks.extend(check.downcast::<CheckButton>().ok());
+    }
+    checks
+}
+
+/// Binds a text cell whose value must be valid for `kind`, marking it while it isn't
+fn bind_checked_text<F: Fn(Option<String>) + 'static>(text: &Text, kind: &ColumnKind, content: Option<&str>, on_change: F) -> CellHandlers {
+    text.set_text(content.unwrap_or(""));
+    if content.map_or(false, |c| kind.check(c).is_none()) {
+        text.add_css_class("error");
+    } else {
+        text.remove_css_class("error");
+    }
+    let kind = kind.clone();
+    let handler = text.connect_text_notify(move |text| {
+        let value = text.text();
+        if value.trim().is_empty() {
+            text.remove_css_class("error");
+            on_change(None);
+        } else if let Some(value) = kind.check(&value) {
+            text.remove_css_class("error");
+            on_change(Some(value));
+        } else {
+            text.add_css_class("error");
+        }
+    });
+    vec![(text.clone().upcast(), handler)]
+}
@@ -0,0 +1,345 @@
+use std::f64::consts::PI;
+use std::fs::File;
+use std::path::Path;
+
+use glib::{clone, BoxedAnyObject};
+use gtk4::{prelude::*, cairo::{self, Context, Format, ImageSurface, SvgSurface}, Button, DrawingArea, DropDown, Label, MenuButton, Orientation, Popover, Window};
+
+use crate::data::table::aggregate::format_number;
+use crate::data::table::chart::{chart_points, Chart, ChartKind};
+use crate::data::table::Layout;
+use crate::data::table::column::ColumnKind;
+use crate::ui::transfer::{save_file_dialog, transfer_error_dialog};
+
+use super::TableEditingState;
+
+/// Labels of the kinds, in the order of [`ChartKind::ALL`]
+const KINDS: [&str; 3] = ["Bar Chart", "Line Chart", "Pie Chart"];
+
+/// Colors of the plotted values, from the GNOME palette
+const PALETTE: [(f64, f64, f64); 6] = [
+    (0.208, 0.518, 0.894),
+    (0.200, 0.820, 0.478),
+    (0.965, 0.827, 0.176),
+    (1.000, 0.471, 0.000),
+    (0.878, 0.106, 0.141),
+    (0.569, 0.255, 0.675),
+];
+
+const CHART_HEIGHT: i32 = 240;
+const MARGIN: f64 = 24.0;
+const FONT_SIZE: f64 = 11.0;
+
+/// The list of charts under the grid, filled by [`update_charts`]
+pub(super) fn create_charts() -> gtk4::Box {
+    gtk4::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(12)
+        .build()
+}
+
+/// The button adding a chart, which starts out plotting the first number column by the first column
+pub(super) fn create_chart_button(state: &TableEditingState) -> Button {
+    let button = Button::builder()
+        .css_classes(["flat"])
+        .label("Add Chart")
+        .build();
+    button.connect_clicked(clone!(@strong state => move |_| {
+        let columns = state.columns.borrow();
+        let values = columns.iter()
+            .find(|c| matches!(c.kind, ColumnKind::Number))
+            .or(columns.last())
+            .map(|c| c.id)
+            .unwrap();
+        state.charts.borrow_mut().push(Chart { kind: ChartKind::Bar, labels: columns[0].id, values });
+        drop(columns);
+        update_charts(&state);
+        // They're under the grid
+        state.layout.set_visible_child_name(Layout::Grid.name());
+    }));
+    button
+}
+
+/// Recreates the charts, leaving out the ones of removed columns
+pub(super) fn update_charts(state: &TableEditingState) {
+    while let Some(child) = state.chart_list.first_child() {
+        state.chart_list.remove(&child);
+    }
+    let columns = state.columns.borrow().iter().map(|c| c.id).collect::<Vec<_>>();
+    state.charts.borrow_mut().retain(|c| columns.contains(&c.labels) && columns.contains(&c.values));
+    let charts = state.charts.borrow().clone();
+    for (n, chart) in charts.iter().enumerate() {
+        state.chart_list.append(&chart_view(state, n, chart));
+    }
+}
+
+/// Draws the charts again, after the rows they plot change
+pub(super) fn redraw_charts(state: &TableEditingState) {
+    let mut child = state.chart_list.first_child();
+    while let Some(chart) = child {
+        if let Some(area) = chart.last_child() {
+            area.queue_draw();
+        }
+        child = chart.next_sibling();
+    }
+}
+
+/// The controls of the `n`th chart, above where it's drawn
+fn chart_view(state: &TableEditingState, n: usize, chart: &Chart) -> gtk4::Box {
+    let names = state.columns.borrow().iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+    let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+    let position = |id| state.columns.borrow().iter().position(|c| c.id == id).unwrap_or(0) as u32;
+
+    let kind = DropDown::from_strings(&KINDS);
+    kind.set_selected(ChartKind::ALL.iter().position(|&k| k == chart.kind).unwrap_or(0) as u32);
+    let values = DropDown::from_strings(&names);
+    values.set_selected(position(chart.values));
+    let labels = DropDown::from_strings(&names);
+    labels.set_selected(position(chart.labels));
+    let area = DrawingArea::builder()
+        .content_height(CHART_HEIGHT)
+        .hexpand(true)
+        .build();
+
+    kind.connect_selected_notify(clone!(@strong state, @strong area => move |kind| {
+        // The chart may have been removed, with its widgets not yet replaced
+        if let (Some(&k), Some(chart)) = (ChartKind::ALL.get(kind.selected() as usize), state.charts.borrow_mut().get_mut(n)) {
+            chart.kind = k;
+        }
+        area.queue_draw();
+    }));
+    for (dropdown, is_values) in [(&values, true), (&labels, false)] {
+        dropdown.connect_selected_notify(clone!(@strong state, @strong area => move |dropdown| {
+            let id = state.columns.borrow().get(dropdown.selected() as usize).map(|c| c.id);
+            if let (Some(id), Some(chart)) = (id, state.charts.borrow_mut().get_mut(n)) {
+                if is_values {
+                    chart.values = id;
+                } else {
+                    chart.labels = id;
+                }
+            }
+            area.queue_draw();
+        }));
+    }
+    area.set_draw_func(clone!(@strong state => move |area, cr, width, height| {
+        let chart = match state.charts.borrow().get(n) {
+            Some(chart) => chart.clone(),
+            None => return,
+        };
+        let color = area.color();
+        let foreground = (color.red() as f64, color.green() as f64, color.blue() as f64);
+        let _ = draw_chart(cr, chart.kind, &points(&state, &chart), width as f64, height as f64, foreground);
+    }));
+
+    let exports = gtk4::Box::builder()
+        .orientation(Orientation::Vertical)
+        .build();
+    let export_button = MenuButton::builder()
+        .css_classes(["flat"])
+        .icon_name("document-save-symbolic")
+        .tooltip_text("Export")
+        .popover(&Popover::builder().child(&exports).build())
+        .build();
+    for (label, extension, name) in [("Export as SVG…", "svg", "SVG Image"), ("Export as PNG…", "png", "PNG Image")] {
+        let button = Button::builder()
+            .css_classes(["flat"])
+            .label(label)
+            .build();
+        button.connect_clicked(clone!(@strong state, @strong area, @strong export_button => move |_| {
+            export_button.popdown();
+            let window = match state.grid.root().and_downcast::<Window>() {
+                Some(window) => window,
+                None => return,
+            };
+            let width = area.width().max(CHART_HEIGHT * 2);
+            save_file_dialog(&window, label, &format!("Chart.{extension}"), name, &[extension], clone!(@strong state, @strong window => move |path| {
+                if let Err(e) = export_chart(&state, n, &path, width) {
+                    transfer_error_dialog(&window, "Couldn't Export Chart", &path, e);
+                }
+            }));
+        }));
+        exports.append(&button);
+    }
+    let remove = Button::builder()
+        .css_classes(["flat"])
+        .icon_name("user-trash-symbolic")
+        .tooltip_text("Remove Chart")
+        .build();
+    remove.connect_clicked(clone!(@strong state => move |remove| {
+        // The charts are only rebuilt on idle, so a second click before then would remove another one
+        remove.set_sensitive(false);
+        let mut charts = state.charts.borrow_mut();
+        if n < charts.len() {
+            charts.remove(n);
+        }
+        drop(charts);
+        // Not while the button being replaced is handling its click
+        glib::idle_add_local_once(clone!(@strong state => move || update_charts(&state)));
+    }));
+
+    let bar = gtk4::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .build();
+    bar.append(&kind);
+    bar.append(&Label::new(Some("of")));
+    bar.append(&values);
+    bar.append(&Label::new(Some("by")));
+    bar.append(&labels);
+    bar.append(&gtk4::Box::builder().hexpand(true).build());
+    bar.append(&export_button);
+    bar.append(&remove);
+
+    let view = gtk4::Box::builder()
+        .css_classes(["table-chart"])
+        .orientation(Orientation::Vertical)
+        .spacing(6)
+        .build();
+    view.append(&bar);
+    view.append(&area);
+    view
+}
+
+/// What the chart plots from the shown rows, in the order they're shown
+fn points(state: &TableEditingState, chart: &Chart) -> Vec<(String, f64)> {
+    let (labels, values) = {
+        let columns = state.columns.borrow();
+        let position = |id| columns.iter().position(|c| c.id == id);
+        match (position(chart.labels), position(chart.values)) {
+            (Some(labels), Some(values)) => (labels, values),
+            _ => return vec![],
+        }
+    };
+    let model = state.model.borrow();
+    chart_points((0..model.n_items())
+        .filter_map(|i| model.item(i).and_downcast::<BoxedAnyObject>())
+        .map(|row| (state.shown_value(&row, labels, chart.labels), state.shown_value(&row, values, chart.values))))
+}
+
+/// Writes the `n`th chart to an SVG or PNG file, by the extension of `path`
+fn export_chart(state: &TableEditingState, n: usize, path: &Path, width: i32) -> Result<(), String> {
+    let chart = state.charts.borrow().get(n).cloned().ok_or("the chart was removed")?;
+    let points = points(state, &chart);
+    let (w, h) = (width as f64, CHART_HEIGHT as f64);
+    let black = (0.0, 0.0, 0.0);
+    if path.extension().map_or(false, |e| e.eq_ignore_ascii_case("png")) {
+        let surface = ImageSurface::create(Format::ARgb32, width, CHART_HEIGHT).map_err(|e| e.to_string())?;
+        draw_chart(&Context::new(&surface).map_err(|e| e.to_string())?, chart.kind, &points, w, h, black).map_err(|e| e.to_string())?;
+        let mut file = File::create(path).map_err(|e| e.to_string())?;
+        surface.write_to_png(&mut file).map_err(|e| e.to_string())
+    } else {
+        let surface = SvgSurface::new(w, h, Some(path)).map_err(|e| e.to_string())?;
+        draw_chart(&Context::new(&surface).map_err(|e| e.to_string())?, chart.kind, &points, w, h, black).map_err(|e| e.to_string())?;
+        surface.finish();
+        Ok(())
+    }
+}
+
+/// Draws `points` in a `width` by `height` area, with text and lines in `foreground`
+fn draw_chart(cr: &Context, kind: ChartKind, points: &[(String, f64)], width: f64, height: f64, foreground: (f64, f64, f64)) -> Result<(), cairo::Error> {
+    let (r, g, b) = foreground;
+    cr.set_font_size(FONT_SIZE);
+    if points.is_empty() || (kind == ChartKind::Pie && points.iter().all(|&(_, v)| v <= 0.0)) {
+        cr.set_source_rgba(r, g, b, 0.55);
+        let text = "No numbers to plot";
+        let extents = cr.text_extents(text)?;
+        cr.move_to((width - extents.width()) / 2.0, height / 2.0);
+        return cr.show_text(text);
+    }
+    match kind {
+        ChartKind::Pie => draw_pie(cr, points, width, height, foreground),
+        _ => draw_axes_chart(cr, kind, points, width, height, foreground),
+    }
+}
+
+/// Bars or a line over the labels, scaled to fit the values and zero
+fn draw_axes_chart(cr: &Context, kind: ChartKind, points: &[(String, f64)], width: f64, height: f64, (r, g, b): (f64, f64, f64)) -> Result<(), cairo::Error> {
+    let min = points.iter().map(|&(_, v)| v).fold(0.0, f64::min);
+    let max = points.iter().map(|&(_, v)| v).fold(0.0, f64::max);
+    let span = if max > min { max - min } else { 1.0 };
+    let (left, right) = (MARGIN, width - MARGIN);
+    let (top, bottom) = (MARGIN, height - MARGIN - FONT_SIZE);
+    let y = |v: f64| top + (max - v) / span * (bottom - top);
+    let slot = (right - left) / points.len() as f64;
+    let x = |i: usize| left + slot * (i as f64 + 0.5);
+
+    cr.set_source_rgba(r, g, b, 0.3);
+    cr.set_line_width(1.0);
+    cr.move_to(left, y(0.0));
+    cr.line_to(right, y(0.0));
+    cr.stroke()?;
+
+    let (pr, pg, pb) = PALETTE[0];
+    cr.set_source_rgb(pr, pg, pb);
+    if kind == ChartKind::Bar {
+        for (i, &(_, v)) in points.iter().enumerate() {
+            let bar = slot * 0.7;
+            cr.rectangle(x(i) - bar / 2.0, y(v.max(0.0)), bar, (y(v) - y(0.0)).abs());
+        }
+        cr.fill()?;
+    } else {
+        cr.set_line_width(2.0);
+        for (i, &(_, v)) in points.iter().enumerate() {
+            cr.line_to(x(i), y(v));
+        }
+        cr.stroke()?;
+        for (i, &(_, v)) in points.iter().enumerate() {
+            cr.arc(x(i), y(v), 3.5, 0.0, 2.0 * PI);
+            cr.fill()?;
+        }
+    }
+
+    cr.set_source_rgb(r, g, b);
+    for (i, (label, v)) in points.iter().enumerate() {
+        // Labels wider than their slot would run into each other
+        let extents = cr.text_extents(label)?;
+        if extents.width() < slot {
+            cr.move_to(x(i) - extents.width() / 2.0, height - MARGIN / 2.0);
+            cr.show_text(label)?;
+        }
+        let value = format_number(*v);
+        let extents = cr.text_extents(&value)?;
+        if extents.width() < slot {
+            let above = if *v < 0.0 { y(*v) + FONT_SIZE + 4.0 } else { y(*v) - 4.0 };
+            cr.move_to(x(i) - extents.width() / 2.0, above);
+            cr.show_text(&value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Slices for the positive values, with a legend beside them
+fn draw_pie(cr: &Context, points: &[(String, f64)], width: f64, height: f64, (r, g, b): (f64, f64, f64)) -> Result<(), cairo::Error> {
+    let points = points.iter().filter(|&&(_, v)| v > 0.0).collect::<Vec<_>>();
+    let total = points.iter().map(|&&(_, v)| v).sum::<f64>();
+    let radius = (height / 2.0 - MARGIN).min(width / 4.0);
+    let (cx, cy) = (MARGIN + radius, height / 2.0);
+    let mut angle = -PI / 2.0;
+    for (i, &&(_, v)) in points.iter().enumerate() {
+        let (pr, pg, pb) = PALETTE[i % PALETTE.len()];
+        let end = angle + v / total * 2.0 * PI;
+        cr.set_source_rgb(pr, pg, pb);
+        cr.move_to(cx, cy);
+        cr.arc(cx, cy, radius, angle, end);
+        cr.close_path();
+        cr.fill()?;
+        angle = end;
+    }
+
+    let line = FONT_SIZE + 6.0;
+    let legend = cx + radius + MARGIN;
+    // As many rows as fit, centered beside the pie
+    let shown = points.len().min(((height - 2.0 * MARGIN) / line) as usize);
+    let mut y = cy - shown as f64 * line / 2.0;
+    for (i, &(label, v)) in points.iter().take(shown).enumerate() {
+        let (pr, pg, pb) = PALETTE[i % PALETTE.len()];
+        cr.set_source_rgb(pr, pg, pb);
+        cr.rectangle(legend, y, FONT_SIZE, FONT_SIZE);
+        cr.fill()?;
+        cr.set_source_rgb(r, g, b);
+        cr.move_to(legend + FONT_SIZE + 6.0, y + FONT_SIZE - 1.0);
+        cr.show_text(&format!("{} ({})", label, format_number(*v)))?;
+        y += line;
+    }
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use glib::{clone, BoxedAnyObject, Bytes};
+use gtk4::{prelude::*, gdk::ContentProvider, gio::Cancellable, Text, Widget};
+
+use crate::data::table::clipboard::{range_from_tsv, range_to_html, range_to_tsv};
+use crate::data::table::column::Column;
+use crate::data::table::row::Row;
+
+use super::TableEditingState;
+use super::columns::new_column_name;
+use super::rows::rows_changed;
+
+/// A rectangle of cells, from where it started to where it ends, by position in the view and column
+pub(super) type CellRange = ((u32, usize), (u32, usize));
+
+fn contains(range: &CellRange, position: u32, i: usize) -> bool {
+    let ((p1, i1), (p2, i2)) = *range;
+    (p1.min(p2)..=p1.max(p2)).contains(&position) && (i1.min(i2)..=i1.max(i2)).contains(&i)
+}
+
+/// Marks the cell at `position` and the column at `i` if it's in the selected range
+pub(super) fn mark_in_range(state: &TableEditingState, widget: &Widget, position: u32, i: usize) {
+    if state.range.borrow().map_or(false, |range| contains(&range, position, i)) {
+        widget.add_css_class("in-range");
+    } else {
+        widget.remove_css_class("in-range");
+    }
+}
+
+/// Selects `range`, or nothing, marking the bound cells in it
+pub(super) fn set_range(state: &TableEditingState, range: Option<CellRange>) {
+    *state.range.borrow_mut() = range;
+    let model = state.model.borrow();
+    let positions = (0..model.n_items())
+        .filter_map(|p| Some((model.item(p).and_downcast::<BoxedAnyObject>()?, p)))
+        .collect::<HashMap<_, _>>();
+    drop(model);
+    let columns = state.columns.borrow().iter().map(|c| c.id).collect::<Vec<_>>();
+    for (column, row, widget) in state.bound.borrow().iter() {
+        if let (Some(&position), Some(i)) = (positions.get(row), columns.iter().position(|c| c == column)) {
+            mark_in_range(state, widget, position, i);
+        }
+    }
+}
+
+/// Puts the shown values of the cells in `range` on the clipboard, as TSV and HTML
+pub(super) fn copy_range(state: &TableEditingState, range: CellRange) {
+    let ((p1, i1), (p2, i2)) = range;
+    let columns = state.columns.borrow().iter().map(|c| c.id).collect::<Vec<_>>();
+    let model = state.model.borrow();
+    let rows = (p1.min(p2)..=p1.max(p2))
+        .filter_map(|p| model.item(p).and_downcast::<BoxedAnyObject>())
+        .map(|row| (i1.min(i2)..=i1.max(i2))
+            .map(|i| state.shown_value(&row, i, columns[i]))
+            .collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let html = Bytes::from_owned(range_to_html(&rows).into_bytes());
+    let provider = ContentProvider::new_union(&[
+        ContentProvider::for_bytes("text/html", &html),
+        ContentProvider::for_value(&range_to_tsv(&rows).to_value()),
+    ]);
+    let _ = state.grid.clipboard().set_content(Some(&provider));
+}
+
+/// Pastes the clipboard from the cell at `position` and the column at `i`.
+/// A single value goes into `text` like any paste would, and ranges fill the cells from there.
+pub(super) fn paste(state: &TableEditingState, position: u32, i: usize, text: Option<Text>) {
+    state.grid.clipboard().read_text_async(None::<&Cancellable>, clone!(@strong state => move |result| {
+        let pasted = match result {
+            Ok(Some(pasted)) => pasted.to_string(),
+            _ => return,
+        };
+        let value = pasted.trim_end_matches(|c| c == '\r' || c == '\n');
+        if !value.contains(|c| c == '\t' || c == '\n' || c == '\r') {
+            if let Some(text) = &text {
+                text.delete_selection();
+                let mut at = text.position();
+                text.insert_text(value, &mut at);
+                text.set_position(at);
+            }
+            return;
+        }
+        paste_range(&state, position, i, range_from_tsv(&pasted));
+    }));
+}
+
+/// Fills the cells from `position` and `i` with `range`, adding the rows and columns it needs.
+/// Values a column doesn't accept are skipped, with the error bell rung once.
+fn paste_range(state: &TableEditingState, position: u32, i: usize, range: Vec<Vec<Option<String>>>) {
+    let width = i + range.iter().map(Vec::len).max().unwrap_or(0);
+    if width > state.columns.borrow().len() {
+        state.edit_content(|content| while content.width() < width {
+            let name = new_column_name(&content.columns);
+            content.insert_column(content.width(), Column::new(name));
+        });
+    }
+    let column_c = state.columns.borrow().len();
+    let model = state.model.borrow();
+    let existing = (position..position + range.len() as u32)
+        .map_while(|p| model.item(p).and_downcast::<BoxedAnyObject>())
+        .collect::<Vec<_>>();
+    drop(model);
+    let indices = state.rows.iter::<BoxedAnyObject>()
+        .filter_map(Result::ok)
+        .enumerate()
+        .map(|(index, row)| (row, index))
+        .collect::<HashMap<_, _>>();
+    let kinds = state.columns.borrow().iter().map(|c| c.kind.clone()).collect::<Vec<_>>();
+    let mut rejected = false;
+    for (n, values) in range.into_iter().enumerate() {
+        let row = existing.get(n).cloned().unwrap_or_else(|| BoxedAnyObject::new(Row::create_empty(column_c)));
+        for (j, value) in values.into_iter().enumerate() {
+            // Values the column can't hold leave the cell as it was, like typing them would
+            let content = match value.filter(|v| !v.is_empty()) {
+                Some(value) => match kinds[i + j].check(&value) {
+                    Some(value) => Some(value),
+                    None => {
+                        rejected = true;
+                        continue;
+                    },
+                },
+                None => None,
+            };
+            row.borrow_mut::<Row>().cells[i + j].content = content;
+        }
+        match indices.get(&row) {
+            // Binds the row's cells again, and sorts and filters it again
+            Some(&index) => state.rows.items_changed(index as u32, 1, 1),
+            None => state.rows.append(&row),
+        }
+    }
+    rows_changed(state);
+    if rejected {
+        state.grid.error_bell();
+    }
+}
@@ -0,0 +1,252 @@
+use adw::{prelude::*, MessageDialog, ResponseAppearance};
+use glib::{clone, VariantTy};
+use gtk4::{gio::{ActionEntry, Menu, MenuItem, SimpleActionGroup}, ColumnViewColumn, Entry, ScrolledWindow, SortType, TextView, Window};
+
+use crate::data::table::column::{Column, ColumnID, ColumnKind};
+
+use super::{TableEditingState, update_columns};
+use super::footer::aggregate_menu;
+use super::links::choose_linked_table;
+
+/// Labels of the column kinds, by [`ColumnKind::name`]
+const KINDS: [(&str, &str); 7] = [
+    ("Text", "text"),
+    ("Number", "number"),
+    ("Date", "date"),
+    ("Checkbox", "boolean"),
+    ("Single Select", "select"),
+    ("Multi Select", "multi-select"),
+    ("URL", "url"),
+];
+
+pub(super) fn column_menu(column: &Column) -> Menu {
+    let id = column.id;
+    let target = id.to_string().to_variant();
+    let section = |items: &[(&str, &str)]| {
+        let section = Menu::new();
+        for (label, action) in items {
+            let item = MenuItem::new(Some(label), None);
+            item.set_action_and_target_value(Some(format!("table.{action}").as_str()), Some(&target));
+            section.append_item(&item);
+        }
+        section
+    };
+    let menu = Menu::new();
+    menu.append_section(None, &section(&[
+        ("Rename…", "rename-column"),
+    ]));
+    let kinds = Menu::new();
+    for (label, kind) in KINDS {
+        let item = MenuItem::new(Some(label), None);
+        item.set_action_and_target_value(Some("table.set-column-type"), Some(&(id.to_string(), kind.to_string()).to_variant()));
+        kinds.append_item(&item);
+    }
+    kinds.append_section(None, &section(&[
+        ("Relation…", "choose-relation-table"),
+        ("Rollup…", "choose-rollup-table"),
+    ]));
+    let kind_section = section(&[
+        ("Edit Options…", "edit-column-options"),
+    ]);
+    kind_section.prepend_submenu(Some("Type"), &kinds);
+    kind_section.append_submenu(Some("Calculate"), &aggregate_menu(column));
+    menu.append_section(None, &kind_section);
+    menu.append_section(None, &section(&[
+        ("Sort Ascending", "sort-ascending"),
+        ("Sort Descending", "sort-descending"),
+    ]));
+    menu.append_section(None, &section(&[
+        (if column.unique { "Allow Repeated Values" } else { "Require Unique Values" }, "toggle-column-unique"),
+        ("Remove Rows with Repeated Values", "dedup-rows"),
+    ]));
+    menu.append_section(None, &section(&[
+        ("Add Column Before", "add-column-before"),
+        ("Add Column After", "add-column-after"),
+    ]));
+    menu.append_section(None, &section(&[
+        ("Move Left", "move-column-left"),
+        ("Move Right", "move-column-right"),
+    ]));
+    menu.append_section(None, &section(&[
+        ("Delete Column", "delete-column"),
+    ]));
+    menu
+}
+
+/// Adds the `table` actions the column header menus use, each taking the column's id
+pub(super) fn add_column_actions(state: &TableEditingState, group: &SimpleActionGroup) {
+    let entry = |name: &str, f: fn(&TableEditingState, usize)| {
+        ActionEntry::builder(name)
+            .parameter_type(Some(VariantTy::STRING))
+            .activate(clone!(@strong state => move |_: &SimpleActionGroup, _, param| {
+                if let Some(i) = param.and_then(|p| p.str()).and_then(|id| column_index(&state, id)) {
+                    f(&state, i);
+                }
+            }))
+            .build()
+    };
+    group.add_action_entries([
+        entry("rename-column", rename_column),
+        entry("edit-column-options", edit_column_options),
+        entry("choose-relation-table", |state, i| choose_linked_table(state, i, false)),
+        entry("choose-rollup-table", |state, i| choose_linked_table(state, i, true)),
+        entry("add-column-before", |state, i| add_column(state, i)),
+        entry("add-column-after", |state, i| add_column(state, i + 1)),
+        entry("move-column-left", |state, i| if i > 0 {
+            state.edit_content(|content| content.move_column(i, i - 1));
+        }),
+        entry("move-column-right", |state, i| if i + 1 < state.columns.borrow().len() {
+            state.edit_content(|content| content.move_column(i, i + 1));
+        }),
+        entry("sort-ascending", |state, i| sort_by(state, i, SortType::Ascending)),
+        entry("sort-descending", |state, i| sort_by(state, i, SortType::Descending)),
+        entry("toggle-column-unique", |state, i| {
+            let unique = state.columns.borrow()[i].unique;
+            state.columns.borrow_mut()[i].unique = !unique;
+            update_columns(state);
+        }),
+        entry("dedup-rows", |state, i| state.edit_content(|content| {
+            content.dedup_rows(i);
+        })),
+        entry("delete-column", |state, i| if state.columns.borrow().len() > 1 {
+            state.edit_content(|content| content.remove_column(i));
+        }),
+    ]);
+    group.add_action_entries([ActionEntry::builder("set-column-type")
+        .parameter_type(Some(VariantTy::new("(ss)").unwrap()))
+        .activate(clone!(@strong state => move |_: &SimpleActionGroup, _, param| {
+            if let Some((id, kind)) = param.and_then(|p| p.get::<(String, String)>()) {
+                if let Some(i) = column_index(&state, &id) {
+                    set_column_kind(&state, i, &kind);
+                }
+            }
+        }))
+        .build()]);
+}
+
+fn column_index(state: &TableEditingState, id: &str) -> Option<usize> {
+    let id = id.parse::<ColumnID>().ok()?;
+    state.columns.borrow().iter().position(|c| c.id == id)
+}
+
+fn sort_by(state: &TableEditingState, i: usize, order: SortType) {
+    let column = state.grid.columns().item(i as u32).and_downcast::<ColumnViewColumn>();
+    state.grid.sort_by_column(column.as_ref(), order);
+}
+
+/// The first "Column N" name none of `columns` has
+pub(super) fn new_column_name(columns: &[Column]) -> String {
+    (columns.len() + 1..)
+        .map(|n| format!("Column {}", n))
+        .find(|name| columns.iter().all(|c| &c.name != name))
+        .unwrap()
+}
+
+fn add_column(state: &TableEditingState, at: usize) {
+    let name = new_column_name(&state.columns.borrow());
+    state.edit_content(|content| content.insert_column(at, Column::new(name)));
+}
+
+fn rename_column(state: &TableEditingState, i: usize) {
+    let entry = Entry::builder()
+        .text(state.columns.borrow()[i].name.as_str())
+        .activates_default(true)
+        .build();
+    let dialog = MessageDialog::builder()
+        .heading("Rename Column")
+        .extra_child(&entry)
+        .default_response("rename")
+        .close_response("cancel")
+        .modal(true)
+        .build();
+    dialog.set_transient_for(state.grid.root().and_downcast::<Window>().as_ref());
+    dialog.add_responses(&[("cancel", "_Cancel"), ("rename", "_Rename")]);
+    dialog.set_response_appearance("rename", ResponseAppearance::Suggested);
+    // Columns need a name to be told apart
+    entry.connect_changed(clone!(@strong dialog => move |entry| {
+        dialog.set_response_enabled("rename", !entry.text().trim().is_empty());
+    }));
+    dialog.connect_response(None, clone!(@strong state, @strong entry => move |_, response| {
+        let name = entry.text().trim().to_string();
+        if response == "rename" && !name.is_empty() {
+            state.columns.borrow_mut()[i].name = name;
+            update_columns(&state);
+        }
+    }));
+    dialog.present();
+}
+
+/// Changes the column's kind, keeping its cells as they are.
+/// Select kinds get the values already in the column as their options.
+fn set_column_kind(state: &TableEditingState, i: usize, name: &str) {
+    let kind = state.columns.borrow()[i].kind.clone();
+    let options = match kind {
+        ColumnKind::Select(options) | ColumnKind::MultiSelect(options) => options,
+        _ => {
+            let content = state.make_table_content();
+            let mut options: Vec<String> = Vec::new();
+            for row in content.cells.chunks(content.width()) {
+                for value in row[i].content.iter().flat_map(|c| c.lines()).map(str::trim) {
+                    if !value.is_empty() && !options.iter().any(|o| o == value) {
+                        options.push(value.to_string());
+                    }
+                }
+            }
+            options
+        },
+    };
+    if let Some(kind) = ColumnKind::from_name(name, options, None) {
+        state.columns.borrow_mut()[i].kind = kind;
+        update_columns(state);
+    }
+}
+
+fn edit_column_options(state: &TableEditingState, i: usize) {
+    let kind = state.columns.borrow()[i].kind.clone();
+    let options = match kind {
+        ColumnKind::Select(options) | ColumnKind::MultiSelect(options) => options.join("\n"),
+        _ => return,
+    };
+    let text = TextView::builder()
+        .top_margin(6)
+        .bottom_margin(6)
+        .left_margin(6)
+        .right_margin(6)
+        .build();
+    text.buffer().set_text(&options);
+    let dialog = MessageDialog::builder()
+        .heading("Edit Options")
+        .body("One option per line")
+        .extra_child(&ScrolledWindow::builder()
+            .css_classes(["card"])
+            .min_content_height(120)
+            .child(&text)
+            .build())
+        .default_response("save")
+        .close_response("cancel")
+        .modal(true)
+        .build();
+    dialog.set_transient_for(state.grid.root().and_downcast::<Window>().as_ref());
+    dialog.add_responses(&[("cancel", "_Cancel"), ("save", "_Save")]);
+    dialog.set_response_appearance("save", ResponseAppearance::Suggested);
+    dialog.connect_response(None, clone!(@strong state, @strong text => move |_, response| {
+        if response != "save" {
+            return;
+        }
+        let buffer = text.buffer();
+        let mut options: Vec<String> = Vec::new();
+        for option in buffer.text(&buffer.start_iter(), &buffer.end_iter(), false).lines().map(str::trim) {
+            if !option.is_empty() && !options.iter().any(|o| o == option) {
+                options.push(option.to_string());
+            }
+        }
+        let mut columns = state.columns.borrow_mut();
+        columns[i].kind = match columns[i].kind {
+            ColumnKind::MultiSelect(_) => ColumnKind::MultiSelect(options),
+            _ => ColumnKind::Select(options),
+        };
+        drop(columns);
+        update_columns(&state);
+    }));
+    dialog.present();
+}
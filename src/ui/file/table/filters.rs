@@ -0,0 +1,145 @@
+use glib::clone;
+use gtk4::{prelude::*, Button, ColumnViewColumn, ColumnViewSorter, DropDown, Entry, FilterChange, MenuButton, Orientation, Popover, SortType};
+
+use crate::data::table::view::{Filter, FilterRule};
+
+use super::TableEditingState;
+
+/// Labels of the filter rules, in the order of [`FilterRule::NAMES`]
+const RULES: [&str; 8] = [
+    "contains",
+    "does not contain",
+    "is",
+    "is not",
+    "is empty",
+    "is not empty",
+    "is greater than",
+    "is less than",
+];
+
+/// The bar above the table, for filtering rows and clearing the sorting
+pub(super) fn create_toolbar(state: &TableEditingState) -> gtk4::Box {
+    let rules = gtk4::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(6)
+        .build();
+    let filter_button = MenuButton::builder()
+        .css_classes(["flat"])
+        .label(filter_label(state.filters.borrow().len()))
+        .popover(&Popover::builder().child(&rules).build())
+        .build();
+    filter_button.popover().unwrap().connect_show(clone!(@strong state, @strong rules => move |_| {
+        update_filter_rows(&state, &rules);
+    }));
+    state.filter.connect_changed(clone!(@strong state, @strong filter_button => move |_, _| {
+        filter_button.set_label(&filter_label(state.filters.borrow().len()));
+    }));
+
+    let clear_sort = Button::builder()
+        .css_classes(["flat"])
+        .label("Clear Sorting")
+        .build();
+    clear_sort.connect_clicked(clone!(@strong state => move |_| {
+        state.grid.sort_by_column(None::<&ColumnViewColumn>, SortType::Ascending);
+    }));
+    if let Some(sorter) = state.grid.sorter().and_downcast::<ColumnViewSorter>() {
+        clear_sort.set_visible(sorter.n_sort_columns() != 0);
+        sorter.connect_changed(clone!(@strong clear_sort => move |sorter, _| {
+            clear_sort.set_visible(sorter.downcast_ref::<ColumnViewSorter>().unwrap().n_sort_columns() != 0);
+        }));
+    }
+
+    let toolbar = gtk4::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .build();
+    toolbar.append(&filter_button);
+    toolbar.append(&clear_sort);
+    toolbar
+}
+
+fn filter_label(count: usize) -> String {
+    match count {
+        0 => "Filter".to_string(),
+        1 => "1 Filter".to_string(),
+        n => format!("{n} Filters"),
+    }
+}
+
+/// Refilters the rows after a change to `state.filters`
+fn apply_filters(state: &TableEditingState) {
+    state.filter.changed(FilterChange::Different);
+}
+
+fn update_filter_rows(state: &TableEditingState, rules: &gtk4::Box) {
+    while let Some(child) = rules.first_child() {
+        rules.remove(&child);
+    }
+    for (n, filter) in state.filters.borrow().iter().enumerate() {
+        rules.append(&filter_row(state, rules, n, filter));
+    }
+    let add = Button::builder()
+        .label("Add Filter")
+        .build();
+    add.connect_clicked(clone!(@strong state, @strong rules => move |_| {
+        let column = state.columns.borrow()[0].id;
+        state.filters.borrow_mut().push(Filter { column, rule: FilterRule::Contains(String::new()) });
+        update_filter_rows(&state, &rules);
+        apply_filters(&state);
+    }));
+    rules.append(&add);
+}
+
+/// The controls for the `n`th filter
+fn filter_row(state: &TableEditingState, rules: &gtk4::Box, n: usize, filter: &Filter) -> gtk4::Box {
+    let names = state.columns.borrow().iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+    let column = DropDown::from_strings(&names.iter().map(String::as_str).collect::<Vec<_>>());
+    if let Some(i) = state.columns.borrow().iter().position(|c| c.id == filter.column) {
+        column.set_selected(i as u32);
+    }
+    let rule = DropDown::from_strings(&RULES);
+    if let Some(i) = FilterRule::NAMES.iter().position(|&name| name == filter.rule.name()) {
+        rule.set_selected(i as u32);
+    }
+    let value = Entry::builder()
+        .text(filter.rule.value().unwrap_or(""))
+        .visible(filter.rule.value().is_some())
+        .build();
+    let remove = Button::builder()
+        .css_classes(["flat"])
+        .icon_name("list-remove-symbolic")
+        .tooltip_text("Remove Filter")
+        .build();
+
+    column.connect_selected_notify(clone!(@strong state => move |column| {
+        if let Some(c) = state.columns.borrow().get(column.selected() as usize) {
+            state.filters.borrow_mut()[n].column = c.id;
+        }
+        apply_filters(&state);
+    }));
+    let set_rule = clone!(@strong state, @strong rule, @strong value => move || {
+        let name = FilterRule::NAMES.get(rule.selected() as usize).copied().unwrap_or("contains");
+        if let Some(r) = FilterRule::from_name(name, value.text().to_string()) {
+            value.set_visible(r.value().is_some());
+            state.filters.borrow_mut()[n].rule = r;
+        }
+        apply_filters(&state);
+    });
+    rule.connect_selected_notify(clone!(@strong set_rule => move |_| set_rule()));
+    value.connect_changed(move |_| set_rule());
+    remove.connect_clicked(clone!(@strong state, @strong rules => move |_| {
+        state.filters.borrow_mut().remove(n);
+        update_filter_rows(&state, &rules);
+        apply_filters(&state);
+    }));
+
+    let row = gtk4::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .build();
+    row.append(&column);
+    row.append(&rule);
+    row.append(&value);
+    row.append(&remove);
+    row
+}
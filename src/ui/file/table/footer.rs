@@ -0,0 +1,139 @@
+use std::cell::Cell;
+
+use glib::{clone, BoxedAnyObject, VariantTy};
+use gtk4::{prelude::*, gio::{ActionEntry, Menu, MenuItem, SimpleActionGroup}, MenuButton, Orientation};
+
+use crate::data::table::aggregate::Aggregate;
+use crate::data::table::column::Column;
+
+use super::{grid_header, TableEditingState};
+
+/// Labels of the aggregates, by [`Aggregate::name`]
+pub(super) const AGGREGATES: [(&str, &str); 7] = [
+    ("Sum", "sum"),
+    ("Average", "average"),
+    ("Min", "min"),
+    ("Max", "max"),
+    ("Count", "count"),
+    ("Count Empty", "count-empty"),
+    ("Count Unique", "count-unique"),
+];
+
+/// The row under the table with each column's aggregate, shown once a column has one
+pub(super) fn create_footer() -> gtk4::Box {
+    gtk4::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .css_classes(["table-footer"])
+        .build()
+}
+
+/// Makes the footer's cells as wide as the header's titles, following them until they've settled.
+/// Column views don't tell when their columns get resized, so this is done whenever cells are bound,
+/// and the titles' widths are read from the header, see [`grid_header`].
+pub(super) fn sync_footer_widths(state: &TableEditingState) {
+    if state.syncing_footer.replace(true) {
+        return;
+    }
+    // The header is laid out after the first tick, so it's only settled once a later one changes nothing
+    let first = Cell::new(true);
+    state.grid.add_tick_callback(clone!(@strong state => move |grid, _| {
+        let mut changed = false;
+        let mut title = grid_header(grid).and_then(|header| header.first_child());
+        let mut cell = state.footer.first_child();
+        while let (Some(t), Some(c)) = (title, cell) {
+            if c.width_request() != t.width() {
+                c.set_width_request(t.width());
+                changed = true;
+            }
+            title = t.next_sibling();
+            cell = c.next_sibling();
+        }
+        if changed || first.replace(false) {
+            glib::Continue(true)
+        } else {
+            state.syncing_footer.set(false);
+            glib::Continue(false)
+        }
+    }));
+}
+
+/// The choice of aggregate for a column, in its header menu and footer cell
+pub(super) fn aggregate_menu(column: &Column) -> Menu {
+    let id = column.id.to_string();
+    let menu = Menu::new();
+    let none = MenuItem::new(Some("None"), None);
+    none.set_action_and_target_value(Some("table.set-column-aggregate"), Some(&(id.clone(), String::new()).to_variant()));
+    menu.append_item(&none);
+    let aggregates = Menu::new();
+    for (label, name) in AGGREGATES {
+        let item = MenuItem::new(Some(label), None);
+        item.set_action_and_target_value(Some("table.set-column-aggregate"), Some(&(id.clone(), name.to_string()).to_variant()));
+        aggregates.append_item(&item);
+    }
+    menu.append_section(None, &aggregates);
+    menu
+}
+
+pub(super) fn add_footer_actions(state: &TableEditingState, group: &SimpleActionGroup) {
+    group.add_action_entries([ActionEntry::builder("set-column-aggregate")
+        .parameter_type(Some(VariantTy::new("(ss)").unwrap()))
+        .activate(clone!(@strong state => move |_: &SimpleActionGroup, _, param| {
+            if let Some((id, name)) = param.and_then(|p| p.get::<(String, String)>()) {
+                let mut columns = state.columns.borrow_mut();
+                if let Some(column) = columns.iter_mut().find(|c| c.id.to_string() == id) {
+                    column.aggregate = Aggregate::from_name(&name);
+                }
+                drop(columns);
+                update_footer_columns(&state);
+            }
+        }))
+        .build()]);
+}
+
+/// Recreates the footer's cells for the current columns
+pub(super) fn update_footer_columns(state: &TableEditingState) {
+    while let Some(child) = state.footer.first_child() {
+        state.footer.remove(&child);
+    }
+    for column in state.columns.borrow().iter() {
+        state.footer.append(&MenuButton::builder()
+            .css_classes(["flat"])
+            .menu_model(&aggregate_menu(column))
+            .build());
+    }
+    update_footer(state);
+    sync_footer_widths(state);
+}
+
+/// Computes the aggregates again, over the rows passing the filters
+pub(super) fn update_footer(state: &TableEditingState) {
+    let columns = state.columns.borrow().clone();
+    state.footer.set_visible(columns.iter().any(|c| c.aggregate.is_some()));
+    if !state.footer.is_visible() {
+        return;
+    }
+    let model = state.model.borrow();
+    let rows = (0..model.n_items())
+        .filter_map(|p| model.item(p).and_downcast::<BoxedAnyObject>())
+        .collect::<Vec<_>>();
+    drop(model);
+    let mut cell = state.footer.first_child();
+    for (i, column) in columns.iter().enumerate() {
+        let button = match cell.and_downcast::<MenuButton>() {
+            Some(button) => button,
+            None => break,
+        };
+        cell = button.next_sibling();
+        let aggregate = match column.aggregate {
+            Some(aggregate) => aggregate,
+            None => {
+                button.set_label("");
+                continue;
+            },
+        };
+        let values = rows.iter().map(|row| state.shown_value(row, i, column.id)).collect::<Vec<_>>();
+        let label = AGGREGATES.iter().find(|(_, name)| *name == aggregate.name()).map_or("", |(label, _)| label);
+        let value = aggregate.compute(&column.kind, values.iter().map(Option::as_deref));
+        button.set_label(&format!("{} {}", label, value.as_deref().unwrap_or("—")));
+    }
+}
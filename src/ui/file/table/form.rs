@@ -0,0 +1,121 @@
+use adw::StatusPage;
+use glib::{clone, BoxedAnyObject};
+use gtk4::{prelude::*, gio::MenuModel, Align, Button, Grid, Label, Orientation};
+
+use crate::data::table::{Layout, column::ColumnKind, row::Row};
+
+use super::TableEditingState;
+use super::cells::{bind_cell, cell_text, create_cell};
+
+/// The page of the form, filled by [`update_form`] while it's shown
+pub(super) fn create_form() -> gtk4::Box {
+    gtk4::Box::builder()
+        .css_classes(["table-form"])
+        .orientation(Orientation::Vertical)
+        .spacing(12)
+        .build()
+}
+
+/// Shows `row` in the form, or the first of the shown rows when there's none
+pub(super) fn show_form(state: &TableEditingState, row: Option<BoxedAnyObject>) {
+    *state.record.borrow_mut() = row;
+    if state.layout.visible_child_name().as_deref() == Some(Layout::Form.name()) {
+        // Not while one of the widgets being replaced is handling an event
+        glib::idle_add_local_once(clone!(@strong state => move || update_form(&state)));
+    } else {
+        state.layout.set_visible_child_name(Layout::Form.name());
+    }
+}
+
+/// Recreates the form's editors for the row being shown, when it's the current layout
+pub(super) fn update_form(state: &TableEditingState) {
+    if state.layout.visible_child_name().as_deref() != Some(Layout::Form.name()) {
+        return;
+    }
+    while let Some(child) = state.form.first_child() {
+        state.form.remove(&child);
+    }
+    let rows = {
+        let model = state.model.borrow();
+        (0..model.n_items())
+            .filter_map(|n| model.item(n).and_downcast::<BoxedAnyObject>())
+            .collect::<Vec<_>>()
+    };
+    // The row stays the one shown if it's filtered out, but there's nothing around it to go to
+    let current = state.record.borrow().clone()
+        .filter(|row| state.row_index(row).is_some())
+        .or_else(|| rows.first().cloned());
+    let row = match current {
+        Some(row) => row,
+        None => {
+            state.form.append(&StatusPage::builder()
+                .icon_name("view-list-symbolic")
+                .title("No Rows")
+                .description("There are no rows to show, the filters might hide them")
+                .vexpand(true)
+                .build());
+            return;
+        },
+    };
+    *state.record.borrow_mut() = Some(row.clone());
+    let position = rows.iter().position(|r| *r == row);
+
+    let bar = gtk4::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .build();
+    let previous = position.filter(|&p| p > 0).map(|p| rows[p - 1].clone());
+    let next = position.and_then(|p| rows.get(p + 1)).cloned();
+    let shown = match position {
+        Some(p) => format!("Row {} of {}", p + 1, rows.len()),
+        None => "Hidden Row".to_string(),
+    };
+    for (icon, tooltip, to) in [("go-previous-symbolic", "Previous Row", previous), ("go-next-symbolic", "Next Row", next)] {
+        let button = Button::builder()
+            .css_classes(["flat"])
+            .icon_name(icon)
+            .tooltip_text(tooltip)
+            .sensitive(to.is_some())
+            .build();
+        button.connect_clicked(clone!(@strong state => move |_| show_form(&state, to.clone())));
+        bar.append(&button);
+    }
+    bar.append(&Label::new(Some(&shown)));
+    state.form.append(&bar);
+
+    let fields = Grid::builder()
+        .column_spacing(12)
+        .row_spacing(6)
+        .build();
+    for (i, column) in state.columns.borrow().iter().enumerate() {
+        fields.attach(&Label::builder()
+            .css_classes(["dim-label"])
+            .label(column.name.as_str())
+            .halign(Align::End)
+            .valign(Align::Center)
+            .build(), 0, i as i32, 1, 1);
+        let editor = create_cell(&column.kind);
+        editor.add_css_class("form-field");
+        editor.set_hexpand(true);
+        if matches!(column.kind, ColumnKind::Boolean) {
+            editor.set_halign(Align::Start);
+        }
+        // The row operations act on the grid's rows, so they're not offered here
+        if let Some(text) = cell_text(&editor) {
+            text.set_extra_menu(None::<&MenuModel>);
+        }
+        let content = match column.kind {
+            ColumnKind::Rollup(_) => state.shown_value(&row, i, column.id),
+            _ => row.borrow::<Row>().cells[i].content.clone(),
+        };
+        bind_cell(&editor, &column.kind, content.as_deref(), state.link_targets(&column.kind), clone!(@strong state, @strong row => move |content| {
+            state.set_cell(&row, i, content);
+            // Rebinds the row's cells in the grid
+            if let Some(index) = state.row_index(&row) {
+                state.rows.items_changed(index as u32, 1, 1);
+            }
+        }));
+        fields.attach(&editor, 1, i as i32, 1, 1);
+    }
+    state.form.append(&fields);
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use adw::{prelude::*, MessageDialog, ResponseAppearance};
+use glib::{clone, BoxedAnyObject};
+use gtk4::{DropDown, Window};
+
+use crate::data::{Category, FileID};
+use crate::data::table::TableContent;
+use crate::data::table::column::ColumnKind;
+use crate::data::table::relation::linked_rows;
+use crate::data::table::row::{Row, RowID};
+use crate::db::DB;
+
+use super::{TableEditingState, update_columns};
+
+/// Rows of a linked table with what links to them show
+pub(super) type LinkTargets = Rc<Vec<(RowID, String)>>;
+
+/// Loads what the relation and rollup columns show from the tables they link with
+pub(super) fn load_links(state: &TableEditingState) {
+    let kinds = state.columns.borrow().iter()
+        .map(|c| c.kind.clone())
+        .filter(|kind| kind.table().is_some())
+        .collect::<Vec<_>>();
+    if kinds.is_empty() {
+        return;
+    }
+    let db = state.db.lock().unwrap();
+    for kind in kinds {
+        match kind {
+            ColumnKind::Relation(table) => {
+                let labels = table_content(state, &db, table).row_labels();
+                state.relations.borrow_mut().insert(table, Rc::new(labels));
+            },
+            ColumnKind::Rollup(table) => {
+                let source = table_content(state, &db, table);
+                let labels = source.row_labels().into_iter().collect::<HashMap<_, _>>();
+                let rollup = source.links_to(state.id).into_iter()
+                    .map(|(row, sources)| (row, sources.iter().filter_map(|s| labels.get(s).cloned()).collect()))
+                    .collect();
+                state.rollups.borrow_mut().insert(table, rollup);
+            },
+            _ => (),
+        }
+    }
+}
+
+/// The content of `table`, which for this table is what's being edited
+fn table_content(state: &TableEditingState, db: &DB, table: FileID) -> TableContent {
+    if table == state.id {
+        state.make_table_content()
+    } else if db.get_file(table).map_or(false, |f| f.category == Category::Table) {
+        db.get_table_content(table)
+    } else {
+        TableContent::default()
+    }
+}
+
+/// What a relation cell shows, the linked rows' labels
+pub(super) fn relation_label(targets: &[(RowID, String)], content: &str) -> String {
+    linked_rows(content).iter()
+        .filter_map(|row| targets.iter().find(|(id, _)| id == row))
+        .map(|(_, label)| label.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Asks which table the column at `i` links with, making it a relation column, or a rollup one
+pub(super) fn choose_linked_table(state: &TableEditingState, i: usize, rollup: bool) {
+    let mut tables = {
+        let db = state.db.lock().unwrap();
+        db.ids().into_iter()
+            .filter_map(|id| db.get_file(id)
+                .filter(|f| f.category == Category::Table)
+                .map(|f| (id, f.title.clone())))
+            .collect::<Vec<_>>()
+    };
+    tables.sort_by_key(|(_, title)| title.to_lowercase());
+    let titles = tables.iter().map(|(_, title)| title.as_str()).collect::<Vec<_>>();
+    let dropdown = DropDown::from_strings(&titles);
+    let current = state.columns.borrow()[i].kind.table();
+    if let Some(n) = tables.iter().position(|(id, _)| Some(*id) == current) {
+        dropdown.set_selected(n as u32);
+    }
+    let (heading, body) = if rollup {
+        ("Show Links from Table", "Cells will show the rows of the table linking to them, by its first column")
+    } else {
+        ("Link to Table", "Cells will link to rows of the table, shown by its first column")
+    };
+    let dialog = MessageDialog::builder()
+        .heading(heading)
+        .body(body)
+        .extra_child(&dropdown)
+        .default_response("choose")
+        .close_response("cancel")
+        .modal(true)
+        .build();
+    dialog.set_transient_for(state.grid.root().and_downcast::<Window>().as_ref());
+    dialog.add_responses(&[("cancel", "_Cancel"), ("choose", "_Choose")]);
+    dialog.set_response_appearance("choose", ResponseAppearance::Suggested);
+    dialog.connect_response(None, clone!(@strong state, @strong dropdown => move |_, response| {
+        if response != "choose" {
+            return;
+        }
+        if let Some((table, _)) = tables.get(dropdown.selected() as usize) {
+            let kind = if rollup { ColumnKind::Rollup(*table) } else { ColumnKind::Relation(*table) };
+            link_cells(&state, i, &kind);
+            state.columns.borrow_mut()[i].kind = kind;
+            update_columns(&state);
+        }
+    }));
+    dialog.present();
+}
+
+/// Turns the column's cells into what `kind` holds, linking the rows whose labels they hold
+fn link_cells(state: &TableEditingState, i: usize, kind: &ColumnKind) {
+    let targets = match kind {
+        ColumnKind::Relation(table) => {
+            let db = state.db.lock().unwrap();
+            table_content(state, &db, *table).row_labels()
+        },
+        _ => vec![],
+    };
+    for row in state.rows.iter::<BoxedAnyObject>().filter_map(Result::ok) {
+        let mut row = row.borrow_mut::<Row>();
+        let cell = &mut row.cells[i];
+        cell.content = match (kind, cell.content.as_deref()) {
+            (ColumnKind::Relation(_), Some(content)) => kind.check(content).or_else(|| {
+                let linked = content.split(|c| c == ',' || c == '\n')
+                    .map(str::trim)
+                    .filter_map(|value| targets.iter().find(|(_, label)| !value.is_empty() && label.eq_ignore_ascii_case(value)))
+                    .map(|(id, _)| id.to_string())
+                    .collect::<Vec<_>>();
+                Some(linked.join("\n")).filter(|l| !l.is_empty())
+            }),
+            _ => None,
+        };
+    }
+}
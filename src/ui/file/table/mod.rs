@@ -1,60 +1,227 @@
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use std::sync::Mutex;
 
 use crate::data::FileID;
-use crate::data::table::TableContent;
-use crate::data::table::column::Column;
-use crate::data::table::row::Row;
+use crate::data::table::{CellRef, Layout, TableContent};
+use crate::data::table::chart::Chart;
+use crate::data::table::column::{Column, ColumnID, ColumnKind};
+use crate::data::table::formula::{is_formula, Value};
+use crate::data::table::pivot::Pivot;
+use crate::data::table::row::{Row, RowID};
+use crate::data::table::view::{compare_values, Filter, SortKey};
 use crate::db::DB;
 use crate::db::Saveable;
 use glib::BoxedAnyObject;
-use glib::Object;
-use glib::SignalHandlerId;
 use glib::clone;
 use gtk4::ColumnView;
 use gtk4::ColumnViewColumn;
+use gtk4::ColumnViewSorter;
+use gtk4::CustomFilter;
+use gtk4::CustomSorter;
+use gtk4::EventControllerFocus;
 use gtk4::EventControllerKey;
-use gtk4::Inhibit;
+use gtk4::FilterChange;
+use gtk4::FilterListModel;
 use gtk4::ListItem;
 use gtk4::MultiSelection;
 use gtk4::Orientation;
+use gtk4::PropagationPhase;
 use gtk4::SelectionModel;
 use gtk4::SignalListItemFactory;
-use gtk4::Text;
-use gtk4::gdk::Key;
-use gtk4::gio::ListStore;
+use gtk4::SortListModel;
+use gtk4::SortType;
+use gtk4::Stack;
+use gtk4::StackSwitcher;
+use gtk4::gio::{ListStore, SimpleActionGroup};
 use gtk4::{prelude::*, Widget};
 
+use self::board::{create_board, update_board};
+use self::calendar::{create_calendar, this_month, update_calendar};
+use self::charts::{create_chart_button, create_charts, redraw_charts, update_charts};
+use self::cells::{bind_cell, create_cell, show_in_cell, CellHandlers};
+use self::clipboard::{mark_in_range, set_range, CellRange};
+use self::columns::{add_column_actions, column_menu};
+use self::form::{create_form, update_form};
+use self::filters::create_toolbar;
+use self::footer::{add_footer_actions, create_footer, sync_footer_widths, update_footer, update_footer_columns};
+use self::links::{load_links, relation_label, LinkTargets};
+use self::nav::handle_key;
+use self::pivot::{add_pivot_actions, create_pivot, update_pivot};
+use self::rows::add_row_actions;
+use self::unique::{create_unique_banner, mark_duplicate, update_duplicates};
 use super::components::create_header;
 
-#[derive(Debug, Clone)]
+mod board;
+mod calendar;
+mod cells;
+mod charts;
+mod clipboard;
+mod columns;
+mod filters;
+mod footer;
+mod form;
+mod links;
+mod nav;
+mod pivot;
+mod rows;
+mod unique;
+
+#[derive(Clone)]
 struct TableEditingState {
+    db: Rc<Mutex<DB>>,
+    id: FileID,
     columns: Rc<RefCell<Vec<Column>>>,
     model: Rc<RefCell<SelectionModel>>,
+    rows: ListStore,
+    grid: ColumnView,
+    /// Repeated values of each unique column
+    duplicates: Rc<RefCell<HashMap<ColumnID, HashSet<String>>>>,
+    /// The cell widgets currently bound, with their column and row
+    bound: Rc<RefCell<Vec<(ColumnID, BoxedAnyObject, Widget)>>>,
+    /// Values of the formula cells
+    values: Rc<RefCell<HashMap<CellRef, Value>>>,
+    filters: Rc<RefCell<Vec<Filter>>>,
+    filter: CustomFilter,
+    /// The cells selected with Shift and the arrows
+    range: Rc<RefCell<Option<CellRange>>>,
+    footer: gtk4::Box,
+    /// Whether the footer is following the widths of the header
+    syncing_footer: Rc<Cell<bool>>,
+    /// Rows of the tables relation columns link to
+    relations: Rc<RefCell<HashMap<FileID, LinkTargets>>>,
+    /// For the tables rollup columns show, what rows of theirs link to each row of this one
+    rollups: Rc<RefCell<HashMap<FileID, HashMap<RowID, Vec<String>>>>>,
+    /// The pages of the layouts, named by [`Layout::name`]
+    layout: Stack,
+    board: gtk4::Box,
+    board_column: Rc<Cell<Option<ColumnID>>>,
+    calendar: gtk4::Box,
+    calendar_column: Rc<Cell<Option<ColumnID>>>,
+    /// The year and month the calendar shows
+    month: Rc<Cell<(i32, u32)>>,
+    form: gtk4::Box,
+    /// The row the form shows
+    record: Rc<RefCell<Option<BoxedAnyObject>>>,
+    charts: Rc<RefCell<Vec<Chart>>>,
+    chart_list: gtk4::Box,
+    pivot: Rc<RefCell<Option<Pivot>>>,
+    pivot_view: gtk4::Box,
 }
 
 impl TableEditingState {
     fn make_table_content(&self) -> TableContent {
         let mut cells = Vec::new();
-        for item in self.model.borrow_mut().iter::<Object>() {
+        let mut rows = Vec::new();
+        for item in self.rows.iter::<BoxedAnyObject>() {
             let mut item = item.unwrap()
-                .downcast::<BoxedAnyObject>()
-                .unwrap()
                 .borrow_mut::<Row>().clone();
             cells.append(&mut item.cells);
+            rows.push(item.id);
         }
         TableContent {
             columns: self.columns.borrow_mut().clone(),
             cells,
+            rows,
+            sort: read_sort(&self.grid),
+            filters: self.filters.borrow().clone(),
+            layout: self.layout.visible_child_name()
+                .and_then(|name| Layout::from_name(&name))
+                .unwrap_or_default(),
+            board_column: self.board_column.get(),
+            calendar_column: self.calendar_column.get(),
+            charts: self.charts.borrow().clone(),
+            pivot: self.pivot.borrow().clone(),
+        }
+    }
+
+    /// Where `row` is stored, which isn't where it's shown when sorted or filtered
+    fn row_index(&self, row: &BoxedAnyObject) -> Option<usize> {
+        self.rows.iter::<BoxedAnyObject>()
+            .position(|r| r.ok().as_ref() == Some(row))
+    }
+
+    /// What the cell shows, which for formulas is their value, and for links the labels of the rows
+    fn shown_value(&self, row: &BoxedAnyObject, i: usize, column: ColumnID) -> Option<String> {
+        let row = row.borrow::<Row>();
+        match self.columns.borrow()[i].kind {
+            ColumnKind::Relation(table) => {
+                let content = row.cells[i].content.as_deref()?;
+                let label = relation_label(self.relations.borrow().get(&table)?, content);
+                return Some(label).filter(|l| !l.is_empty());
+            },
+            ColumnKind::Rollup(table) => {
+                let labels = self.rollups.borrow().get(&table)?.get(&row.id)?.join(", ");
+                return Some(labels).filter(|l| !l.is_empty());
+            },
+            _ => (),
+        }
+        let content = row.cells[i].content.clone()?;
+        if is_formula(&content) {
+            self.values.borrow().get(&CellRef { column, row: row.id }).map(|v| v.to_string())
+        } else {
+            Some(content)
+        }
+    }
+
+    /// Rows the cells of a `kind` column can link to
+    fn link_targets(&self, kind: &ColumnKind) -> LinkTargets {
+        match kind {
+            ColumnKind::Relation(table) => self.relations.borrow().get(table).cloned().unwrap_or_default(),
+            _ => LinkTargets::default(),
+        }
+    }
+
+    /// Puts `content` in the `i`th cell of `row`, updating what depends on it
+    fn set_cell(&self, row: &BoxedAnyObject, i: usize, content: Option<String>) {
+        let formula = content.as_deref().map_or(false, is_formula);
+        row.borrow_mut::<Row>().cells[i].content = content;
+        if self.columns.borrow()[i].unique {
+            update_duplicates(self, i);
+        }
+        let recomputed = formula || !self.values.borrow().is_empty();
+        if recomputed {
+            update_formulas(self);
+        }
+        // Formulas can change what any column shows, otherwise it's only this one
+        let columns = self.columns.borrow();
+        let changed = |id: ColumnID| recomputed || id == columns[i].id;
+        let aggregated = columns.iter().any(|c| c.aggregate.is_some() && changed(c.id));
+        let charted = self.charts.borrow().iter().any(|c| changed(c.labels) || changed(c.values));
+        drop(columns);
+        if aggregated {
+            update_footer(self);
+        }
+        if charted {
+            redraw_charts(self);
         }
     }
+
+    /// Puts `content` in the view, recreating its columns
+    fn set_table_content(&self, mut content: TableContent) {
+        *self.columns.borrow_mut() = content.columns.clone();
+        *self.filters.borrow_mut() = content.filters.clone();
+        *self.charts.borrow_mut() = content.charts.clone();
+        self.rows.remove_all();
+        for row in content.take_rows() {
+            self.rows.append(&BoxedAnyObject::new(row));
+        }
+        update_columns(self);
+    }
+
+    /// Applies `edit` to the table's content, for changes that move cells around
+    fn edit_content<F: FnOnce(&mut TableContent)>(&self, edit: F) {
+        let mut content = self.make_table_content();
+        edit(&mut content);
+        self.set_table_content(content);
+    }
 }
 
 impl Saveable for TableEditingState {
-    fn save(&self, root: String, id: FileID) {
-        self.make_table_content().save(root, id)
+    fn save(&self, db: &mut DB, id: FileID) {
+        self.make_table_content().save(db, id)
     }
 }
 
@@ -66,6 +233,9 @@ pub fn display_table<F: Fn(String) + 'static>(
     mut content: TableContent,
     on_rename: F,
 ) -> (Widget, Box<dyn Saveable>) {
+    if content.columns.is_empty() {
+        content.columns.push(Column::new("Column 1".to_string()));
+    }
     if content.is_empty() {
         content.insert_row(0);
     }
@@ -77,90 +247,295 @@ pub fn display_table<F: Fn(String) + 'static>(
         // .halign(Align::Center)
         .build();
     view.append(&create_header(db, id, title, keywords, on_rename));
+    view.append(&create_unique_banner(&content));
 
     let grid = ColumnView::builder()
         .enable_rubberband(true)
         .build();
 
+    let sort = content.sort.clone();
     let list_model = ListStore::new(BoxedAnyObject::static_type());
     for row in content.take_rows() {
         list_model.append(&BoxedAnyObject::new(row.clone()));
     }
-    let model = MultiSelection::new(Some(list_model.clone()));
+    // Sorting and filtering only change what's shown, rows stay in the list store in their stored order
+    let filter = CustomFilter::new(|_| true);
+    let filtered = FilterListModel::new(Some(list_model.clone()), Some(filter.clone()));
+    let sorted = SortListModel::new(Some(filtered), grid.sorter());
+    let model = MultiSelection::new(Some(sorted));
 
     grid.set_model(Some(&model));
 
     let state = TableEditingState {
+        db: db.clone(),
+        id,
         columns: Rc::new(RefCell::new(content.columns)),
         model: Rc::new(RefCell::new(model.upcast())),
+        rows: list_model,
+        grid: grid.clone(),
+        duplicates: Rc::new(RefCell::new(HashMap::new())),
+        bound: Rc::new(RefCell::new(Vec::new())),
+        values: Rc::new(RefCell::new(HashMap::new())),
+        filters: Rc::new(RefCell::new(content.filters)),
+        filter,
+        range: Rc::new(RefCell::new(None)),
+        footer: create_footer(),
+        syncing_footer: Rc::new(Cell::new(false)),
+        relations: Rc::new(RefCell::new(HashMap::new())),
+        rollups: Rc::new(RefCell::new(HashMap::new())),
+        layout: Stack::new(),
+        board: create_board(),
+        board_column: Rc::new(Cell::new(content.board_column)),
+        calendar: create_calendar(),
+        calendar_column: Rc::new(Cell::new(content.calendar_column)),
+        month: Rc::new(Cell::new(this_month())),
+        form: create_form(),
+        record: Rc::new(RefCell::new(None)),
+        charts: Rc::new(RefCell::new(content.charts)),
+        chart_list: create_charts(),
+        pivot: Rc::new(RefCell::new(content.pivot)),
+        pivot_view: create_pivot(),
     };
+    state.filter.set_filter_func(clone!(@strong state => move |row| {
+        let row = row.downcast_ref::<BoxedAnyObject>().unwrap();
+        let columns = state.columns.borrow();
+        state.filters.borrow().iter().all(|filter| {
+            match columns.iter().position(|c| c.id == filter.column) {
+                Some(i) => filter.rule.matches(&columns[i].kind, state.shown_value(row, i, filter.column).as_deref()),
+                None => true,
+            }
+        })
+    }));
+    state.filter.connect_changed(clone!(@strong state => move |_, _| {
+        update_footer(&state);
+        redraw_charts(&state);
+        update_board(&state);
+        update_calendar(&state);
+        update_form(&state);
+        update_pivot(&state);
+    }));
+    update_columns(&state);
+    apply_sort(&state, &sort);
+    let actions = SimpleActionGroup::new();
+    add_column_actions(&state, &actions);
+    add_row_actions(&state, &actions);
+    add_footer_actions(&state, &actions);
+    add_pivot_actions(&state, &actions);
+    // On the whole view, as the footer's menus are outside the grid
+    view.insert_action_group("table", Some(&actions));
 
-    let column_c = state.columns.borrow_mut().len();
-    for (i, column) in state.columns.borrow_mut().iter().enumerate() {
-        let factory = SignalListItemFactory::new();
-        factory.connect_setup(clone!(@strong list_model => move |_, item| {
-            let item = item
-                .downcast_ref::<ListItem>().unwrap();
-            let widget = Text::builder()
-                .editable(true)
-                .css_name("cell")
-                .build();
-            let controller = EventControllerKey::new();
-            controller.connect_key_pressed(clone!(@strong list_model, @strong item => move |_, key, _, _| {
-                match key {
-                    Key::Return => {
-                        list_model.insert(item.position() + 1, &BoxedAnyObject::new(Row::create_empty(column_c)));
-                        Inhibit(true)
-                    },
-                    _ => Inhibit(false),
-                }
-            }));
-            widget.add_controller(controller);
-            item.set_child(Some(&widget));
+    let table = gtk4::Box::builder()
+        .orientation(Orientation::Vertical)
+        .build();
+    table.append(&grid);
+    table.append(&state.footer);
+    state.footer.connect_map(clone!(@strong state => move |_| sync_footer_widths(&state)));
+    table.append(&state.chart_list);
+    state.layout.add_titled(&table, Some(Layout::Grid.name()), "Grid");
+    state.layout.add_titled(&state.board, Some(Layout::Board.name()), "Board");
+    state.layout.add_titled(&state.calendar, Some(Layout::Calendar.name()), "Calendar");
+    state.layout.add_titled(&state.form, Some(Layout::Form.name()), "Form");
+    state.layout.add_titled(&state.pivot_view, Some(Layout::Pivot.name()), "Summary");
+    state.layout.set_visible_child_name(content.layout.name());
+    state.layout.connect_visible_child_name_notify(clone!(@strong state => move |_| {
+        update_board(&state);
+        update_calendar(&state);
+        update_form(&state);
+        update_pivot(&state);
+    }));
+    update_board(&state);
+    update_calendar(&state);
+    update_form(&state);
+    update_pivot(&state);
+
+    let toolbar = create_toolbar(&state);
+    toolbar.prepend(&StackSwitcher::builder().stack(&state.layout).build());
+    toolbar.append(&create_chart_button(&state));
+    view.append(&toolbar);
+    view.append(&state.layout);
+
+    (view.upcast(), Box::new(state))
+}
+
+/// Replaces the view's columns with ones for the current `columns`
+fn update_columns(state: &TableEditingState) {
+    let sort = read_sort(&state.grid);
+    let old = state.grid.columns();
+    let old = (0..old.n_items())
+        .filter_map(|i| old.item(i).and_downcast::<ColumnViewColumn>())
+        .collect::<Vec<_>>();
+    for column in old {
+        state.grid.remove_column(&column);
+    }
+    let n = state.columns.borrow().len();
+    for i in 0..n {
+        update_duplicates(state, i);
+    }
+    load_links(state);
+    update_formulas(state);
+    for (i, column) in state.columns.borrow().iter().enumerate() {
+        state.grid.append_column(&create_column(state, i, column));
+    }
+    apply_sort(state, &sort);
+    let columns = state.columns.borrow().iter().map(|c| c.id).collect::<Vec<_>>();
+    state.filters.borrow_mut().retain(|f| columns.contains(&f.column));
+    state.filter.changed(FilterChange::Different);
+    update_footer_columns(state);
+    update_charts(state);
+    update_board(state);
+    update_calendar(state);
+    update_form(state);
+    update_pivot(state);
+}
+
+/// The column view's header, the row of column titles above its rows.
+/// GTK 4.10 has no API for where columns are laid out, `ColumnViewColumn` only has a fixed width set by code,
+/// nor for scrolling to a row, `ColumnView::scroll_to` came with 4.12. So the footer widths and [`nav`]'s
+/// scrolling are worked out from the header, found among the column view's own children until GTK has those.
+fn grid_header(grid: &ColumnView) -> Option<Widget> {
+    grid.first_child().filter(|header| header.css_name() == "header")
+}
+
+/// The columns the view is sorted by, the first one sorting first
+fn read_sort(grid: &ColumnView) -> Vec<SortKey> {
+    let sorter = match grid.sorter().and_downcast::<ColumnViewSorter>() {
+        Some(sorter) => sorter,
+        None => return vec![],
+    };
+    (0..sorter.n_sort_columns()).filter_map(|i| {
+        let (column, order) = sorter.nth_sort_column(i);
+        Some(SortKey {
+            column: column?.id()?.parse().ok()?,
+            descending: order == SortType::Descending,
+        })
+    }).collect()
+}
+
+fn apply_sort(state: &TableEditingState, sort: &[SortKey]) {
+    let columns = state.grid.columns();
+    let columns = (0..columns.n_items())
+        .filter_map(|i| columns.item(i).and_downcast::<ColumnViewColumn>())
+        .collect::<Vec<_>>();
+    // Each column sorted by becomes the first key, so the first key goes last
+    for key in sort.iter().rev() {
+        let column = columns.iter().find(|c| c.id().map_or(false, |id| id.as_str() == key.column.to_string()));
+        if let Some(column) = column {
+            state.grid.sort_by_column(Some(column), if key.descending { SortType::Descending } else { SortType::Ascending });
+        }
+    }
+}
+
+/// Evaluates the formulas again, showing the new values in the cells not being edited
+fn update_formulas(state: &TableEditingState) {
+    let values = state.make_table_content().evaluate_formulas();
+    for (column, row, widget) in state.bound.borrow().iter() {
+        let at = CellRef { column: *column, row: row.borrow::<Row>().id };
+        if let Some(value) = values.get(&at) {
+            if !widget.has_focus() {
+                show_in_cell(widget, &value.to_string());
+            }
+        }
+    }
+    *state.values.borrow_mut() = values;
+}
+
+fn create_column(state: &TableEditingState, i: usize, column: &Column) -> ColumnViewColumn {
+    let factory = SignalListItemFactory::new();
+    factory.connect_setup(clone!(@strong state, @strong column => move |_, item| {
+        let item = item
+            .downcast_ref::<ListItem>().unwrap();
+        let widget = create_cell(&column.kind);
+        // What the cell held when it got focus, for Escape to put back
+        let original = Rc::new(RefCell::new(None::<String>));
+        let controller = EventControllerKey::new();
+        // Before the cell's own handling, which would take the arrows and Tab
+        controller.set_propagation_phase(PropagationPhase::Capture);
+        controller.connect_key_pressed(clone!(@strong item, @strong state, @strong original => move |_, key, _, modifiers| {
+            handle_key(&state, &item, i, original.borrow().as_deref(), key, modifiers)
         }));
-        factory.connect_bind(clone!(@strong list_model, @strong state, @strong column => move |_, item| {
-            let item = item
-                .downcast_ref::<ListItem>().unwrap();
-            let text = item.child()
-                .and_downcast::<Text>().unwrap();
-            let row = item.item()
-                .and_downcast::<BoxedAnyObject>()
-                .unwrap()
-                .borrow_mut::<Row>().clone();
-            let cell = row.get_cell(&state.columns.borrow_mut(), column.id).unwrap();
-            text.set_text(match &cell.content {
-                Some(c) => c,
-                None => "",
-            });
-            let position = item.position();
-            let handler_id = text.connect_text_notify(clone!(@strong list_model => move |text| {
-                let row = list_model.item(position)
-                    .and_downcast::<BoxedAnyObject>().unwrap();
-                let mut row = row.borrow_mut::<Row>();
-                row.cells[i].content = Some(text.text().to_string());
-            }));
-            unsafe {
-                text.set_data("text-notify-signal", handler_id);
+        widget.add_controller(controller);
+        // Formulas are shown while editing, and their values otherwise
+        let focus = EventControllerFocus::new();
+        focus.connect_enter(clone!(@strong item, @strong state, @strong original => move |focus| {
+            // Moving anywhere but to the end of the range leaves it
+            let range = *state.range.borrow();
+            if range.map_or(false, |(_, end)| end != (item.position(), i)) {
+                set_range(&state, None);
+            }
+            if let Some(row) = item.item().and_downcast::<BoxedAnyObject>() {
+                let content = row.borrow::<Row>().cells[i].content.clone();
+                if let Some(formula) = content.as_deref().filter(|c| is_formula(c)) {
+                    show_in_cell(&focus.widget(), formula);
+                }
+                *original.borrow_mut() = content;
             }
         }));
-        factory.connect_unbind(|_, item| {
-            let item = item
-                .downcast_ref::<ListItem>().unwrap();
-            let text = item.child()
-                .and_downcast::<Text>().unwrap();
-            unsafe {
-                let handler_id = text.steal_data::<SignalHandlerId>("text-notify-signal").unwrap();
-                text.disconnect(handler_id);
+        focus.connect_leave(clone!(@strong item, @strong state, @strong column => move |focus| {
+            if let Some(row) = item.item().and_downcast::<BoxedAnyObject>() {
+                let formula = row.borrow::<Row>().cells[i].content.as_deref().map_or(false, is_formula);
+                if let Some(value) = state.shown_value(&row, i, column.id).filter(|_| formula) {
+                    show_in_cell(&focus.widget(), &value);
+                }
             }
+        }));
+        widget.add_controller(focus);
+        item.set_child(Some(&widget));
+    }));
+    factory.connect_bind(clone!(@strong state, @strong column => move |_, item| {
+        let item = item
+            .downcast_ref::<ListItem>().unwrap();
+        let widget = item.child().unwrap();
+        let row_object = item.item()
+            .and_downcast::<BoxedAnyObject>()
+            .unwrap();
+        let row = row_object.borrow_mut::<Row>().clone();
+        let cell = row.get_cell(&state.columns.borrow_mut(), column.id).unwrap();
+        let content = match column.kind {
+            ColumnKind::Rollup(_) => state.shown_value(&row_object, i, column.id),
+            _ => cell.content.clone(),
+        };
+        let handlers = bind_cell(&widget, &column.kind, content.as_deref(), state.link_targets(&column.kind), clone!(@strong row_object, @strong state => move |content| {
+            state.set_cell(&row_object, i, content);
+        }));
+        let duplicate = cell.content.as_ref().map_or(false, |c| {
+            state.duplicates.borrow().get(&column.id).map_or(false, |d| d.contains(c))
         });
-        let column_view = ColumnViewColumn::builder()
-            .title(column.name.as_str())
-            .factory(&factory)
-            .build();
-        grid.append_column(&column_view);
-    }
-
-    view.append(&grid);
-
-    (view.upcast(), Box::new(state))
-}
\ No newline at end of file
+        mark_duplicate(&widget, duplicate);
+        mark_in_range(&state, &widget, item.position(), i);
+        state.bound.borrow_mut().push((column.id, row_object, widget.clone()));
+        // The cell may make its column wider
+        sync_footer_widths(&state);
+        unsafe {
+            widget.set_data("cell-signals", handlers);
+        }
+        if cell.content.as_deref().map_or(false, is_formula) && !widget.has_focus() {
+            if let Some(value) = state.shown_value(&row_object, i, column.id) {
+                show_in_cell(&widget, &value);
+            }
+        }
+    }));
+    factory.connect_unbind(clone!(@strong state => move |_, item| {
+        let item = item
+            .downcast_ref::<ListItem>().unwrap();
+        let widget = item.child().unwrap();
+        state.bound.borrow_mut().retain(|(_, _, w)| w != &widget);
+        unsafe {
+            let handlers = widget.steal_data::<CellHandlers>("cell-signals").unwrap();
+            for (object, handler_id) in handlers {
+                object.disconnect(handler_id);
+            }
+        }
+    }));
+    let sorter = CustomSorter::new(clone!(@strong state, @strong column => move |a, b| {
+        let a = state.shown_value(a.downcast_ref::<BoxedAnyObject>().unwrap(), i, column.id);
+        let b = state.shown_value(b.downcast_ref::<BoxedAnyObject>().unwrap(), i, column.id);
+        compare_values(&column.kind, a.as_deref(), b.as_deref()).into()
+    }));
+    ColumnViewColumn::builder()
+        .id(column.id.to_string())
+        .title(column.name.as_str())
+        .factory(&factory)
+        .sorter(&sorter)
+        .header_menu(&column_menu(column))
+        .build()
+}
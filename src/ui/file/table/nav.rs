@@ -0,0 +1,205 @@
+use glib::{clone, BoxedAnyObject};
+use gtk4::{prelude::*, gdk::{Key, ModifierType}, Inhibit, ListItem, Popover};
+
+use crate::data::table::row::Row;
+
+use super::{grid_header, TableEditingState, update_formulas};
+use super::cells::cell_text;
+use super::clipboard::{copy_range, paste, set_range};
+use super::footer::update_footer;
+
+/// Moves between cells like a spreadsheet does, from the cell of the column at `i` in `item`.
+/// Arrows, Home and End move the text cursor first, and leave the cell once it's at that end.
+/// Escape puts back `original`, what the cell held when it was focused.
+/// Shift and the arrows select a range of cells, for copying, and pasting starts at this cell.
+pub(super) fn handle_key(
+    state: &TableEditingState,
+    item: &ListItem,
+    i: usize,
+    original: Option<&str>,
+    key: Key,
+    modifiers: ModifierType,
+) -> Inhibit {
+    let widget = match item.child() {
+        Some(widget) => widget,
+        None => return Inhibit(false),
+    };
+    // Keys in the cell's popovers, like the date picker, are theirs
+    let focus = widget.root().and_then(|root| root.focus());
+    if focus.map_or(false, |focus| focus.ancestor(Popover::static_type()).is_some()) {
+        return Inhibit(false);
+    }
+    let text = cell_text(&widget);
+    let position = item.position();
+    let width = state.columns.borrow().len();
+    let last_row = state.model.borrow().n_items().saturating_sub(1);
+    let ctrl = modifiers.contains(ModifierType::CONTROL_MASK);
+    let shift = modifiers.contains(ModifierType::SHIFT_MASK);
+    let selecting = text.as_ref().map_or(false, |t| t.selection_bounds().is_some());
+    let at_start = text.as_ref().map_or(true, |t| !selecting && t.position() == 0);
+    let at_end = text.as_ref().map_or(true, |t| !selecting && t.position() as usize == t.text().chars().count());
+
+    // Shift and the arrows select a range of cells, once they'd leave the text
+    let range = *state.range.borrow();
+    if shift && matches!(key, Key::Up | Key::Down | Key::Left | Key::Right) {
+        let (anchor, (position, i)) = range.unwrap_or(((position, i), (position, i)));
+        let extending = range.is_some();
+        let end = match key {
+            Key::Up => position.checked_sub(1).map(|p| (p, i)),
+            Key::Down => Some(position + 1).filter(|&p| p <= last_row).map(|p| (p, i)),
+            Key::Left if extending || at_start => i.checked_sub(1).map(|i| (position, i)),
+            Key::Right if extending || at_end => Some(i + 1).filter(|&i| i < width).map(|i| (position, i)),
+            _ => return Inhibit(false),
+        };
+        if let Some((position, i)) = end {
+            set_range(state, Some((anchor, (position, i))));
+            focus_cell(state, position, i);
+        }
+        return Inhibit(true);
+    }
+
+    let target = match key {
+        Key::Up => position.checked_sub(1).map(|p| (p, i)),
+        Key::Down => Some(position + 1).filter(|&p| p <= last_row).map(|p| (p, i)),
+        Key::Left if at_start => i.checked_sub(1).map(|i| (position, i)),
+        Key::Right if at_end => Some(i + 1).filter(|&i| i < width).map(|i| (position, i)),
+        Key::Tab | Key::ISO_Left_Tab if shift || key == Key::ISO_Left_Tab => match (i, position) {
+            (0, 0) => None,
+            (0, p) => Some((p - 1, width - 1)),
+            (i, p) => Some((p, i - 1)),
+        },
+        Key::Tab => if i + 1 < width {
+            Some((position, i + 1))
+        } else if position < last_row {
+            Some((position + 1, 0))
+        } else {
+            None
+        },
+        Key::Home if ctrl => Some((0, 0)),
+        Key::End if ctrl => Some((last_row, width - 1)),
+        Key::Home if at_start => Some((position, 0)),
+        Key::End if at_end => Some((position, width - 1)),
+        Key::Return | Key::KP_Enter if shift => position.checked_sub(1).map(|p| (p, i)),
+        Key::Return | Key::KP_Enter => if position < last_row {
+            Some((position + 1, i))
+        } else {
+            // Going down from the last row makes a new one
+            if let Some(row) = item.item().and_downcast::<BoxedAnyObject>() {
+                append_row_after(state, &row, i);
+            }
+            None
+        },
+        Key::c | Key::C if ctrl => match range {
+            Some(range) => {
+                copy_range(state, range);
+                None
+            },
+            None => return Inhibit(false),
+        },
+        Key::v | Key::V if ctrl => {
+            paste(state, position, i, text.clone());
+            None
+        },
+        Key::Escape => {
+            set_range(state, None);
+            if let Some(text) = &text {
+                text.set_text(original.unwrap_or(""));
+                text.select_region(0, -1);
+            }
+            None
+        },
+        _ => return Inhibit(false),
+    };
+    if let Some((position, i)) = target {
+        focus_cell(state, position, i);
+    }
+    Inhibit(true)
+}
+
+/// Focuses the cell at `position` in the view and the column at `i`, scrolling to it if its row isn't shown
+pub(super) fn focus_cell(state: &TableEditingState, position: u32, i: usize) {
+    if !grab_cell(state, position, i) {
+        scroll_to(state, position);
+        // The row gets its widgets once the view is laid out again
+        glib::idle_add_local_once(clone!(@strong state => move || {
+            grab_cell(&state, position, i);
+        }));
+    }
+}
+
+fn grab_cell(state: &TableEditingState, position: u32, i: usize) -> bool {
+    let row = match state.model.borrow().item(position).and_downcast::<BoxedAnyObject>() {
+        Some(row) => row,
+        None => return false,
+    };
+    let column = match state.columns.borrow().get(i) {
+        Some(column) => column.id,
+        None => return false,
+    };
+    let widget = state.bound.borrow().iter()
+        .find(|(c, r, _)| *c == column && *r == row)
+        .map(|(_, _, widget)| widget.clone());
+    match widget {
+        Some(widget) => {
+            match cell_text(&widget) {
+                Some(text) => text.grab_focus(),
+                None => widget.grab_focus(),
+            };
+            true
+        },
+        None => false,
+    }
+}
+
+/// Scrolls the view so the row at `position` is shown, until GTK has a way to, see [`grid_header`].
+/// Rows are all as high, so where it is comes from the rows that have cells bound.
+fn scroll_to(state: &TableEditingState, position: u32) {
+    let adjustment = match state.grid.vadjustment() {
+        Some(adjustment) => adjustment,
+        None => return,
+    };
+    let bound = state.bound.borrow().iter()
+        .filter_map(|(_, row, widget)| Some((row.clone(), widget.compute_bounds(&state.grid)?)))
+        .collect::<Vec<_>>();
+    let model = state.model.borrow();
+    let shown = (0..model.n_items())
+        .filter_map(|p| {
+            let row = model.item(p).and_downcast::<BoxedAnyObject>()?;
+            bound.iter().find(|(r, _)| *r == row).map(|(_, bounds)| (p, bounds.y() as f64, bounds.height() as f64))
+        })
+        .take(2)
+        .collect::<Vec<_>>();
+    drop(model);
+    let (known, y, height) = match shown.first() {
+        Some(&first) => first,
+        None => return,
+    };
+    let height = match shown.get(1) {
+        Some(&(p, other, _)) => (other - y) / (p - known) as f64,
+        None => height,
+    };
+    // Bounds are from the top of the grid, and the list is under the header
+    let header = grid_header(&state.grid).map_or(0, |header| header.height()) as f64;
+    let top = adjustment.value() + y - header + (position as f64 - known as f64) * height;
+    if top < adjustment.value() {
+        adjustment.set_value(top);
+    } else if top + height > adjustment.value() + adjustment.page_size() {
+        adjustment.set_value(top + height - adjustment.page_size());
+    }
+}
+
+fn append_row_after(state: &TableEditingState, row: &BoxedAnyObject, i: usize) {
+    let at = state.row_index(row).map_or(state.rows.n_items(), |i| i as u32 + 1);
+    let new = BoxedAnyObject::new(Row::create_empty(state.columns.borrow().len()));
+    state.rows.insert(at, &new);
+    if !state.values.borrow().is_empty() {
+        update_formulas(state);
+    }
+    update_footer(state);
+    let model = state.model.borrow();
+    let position = (0..model.n_items()).find(|&p| model.item(p).and_downcast::<BoxedAnyObject>().as_ref() == Some(&new));
+    drop(model);
+    if let Some(position) = position {
+        focus_cell(state, position, i);
+    }
+}
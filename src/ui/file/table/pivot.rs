@@ -0,0 +1,148 @@
+use adw::StatusPage;
+use glib::clone;
+use gtk4::{prelude::*, gio::{ActionEntry, SimpleActionGroup}, Align, Button, DropDown, Grid, Label, Orientation};
+
+use crate::data::Category;
+use crate::data::table::{Layout, aggregate::Aggregate, column::{Column, ColumnKind}, pivot::Pivot};
+
+use super::TableEditingState;
+use super::footer::AGGREGATES;
+
+/// The page of the summary, filled by [`update_pivot`] while it's shown
+pub(super) fn create_pivot() -> gtk4::Box {
+    gtk4::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(12)
+        .build()
+}
+
+/// Adds `save-pivot` to the `table` actions, making a new table file of the summary and opening it
+pub(super) fn add_pivot_actions(state: &TableEditingState, group: &SimpleActionGroup) {
+    group.add_action_entries([ActionEntry::builder("save-pivot")
+        .activate(clone!(@strong state => move |_: &SimpleActionGroup, _, _| {
+            let summary = match state.pivot.borrow().as_ref().and_then(|pivot| state.make_table_content().pivot(pivot)) {
+                Some(summary) => summary,
+                None => return,
+            };
+            let id = {
+                let mut db = state.db.lock().unwrap();
+                let title = db.get_file(state.id).map_or("Summary".to_string(), |f| format!("{} Summary", f.title));
+                let id = db.new_file(title, Category::Table);
+                db.set_table_content(id, &summary);
+                id
+            };
+            let _ = state.grid.activate_action("win.open-file", Some(&id.to_string().to_variant()));
+        }))
+        .build()]);
+}
+
+/// Columns that can be grouped by or aggregated, which are the ones holding their own values
+fn summable(column: &Column) -> bool {
+    !matches!(column.kind, ColumnKind::Relation(_) | ColumnKind::Rollup(_))
+}
+
+/// Recomputes the summary from the rows, when it's the current layout
+pub(super) fn update_pivot(state: &TableEditingState) {
+    if state.layout.visible_child_name().as_deref() != Some(Layout::Pivot.name()) {
+        return;
+    }
+    while let Some(child) = state.pivot_view.first_child() {
+        state.pivot_view.remove(&child);
+    }
+    let columns = state.columns.borrow().iter()
+        .filter(|c| summable(c))
+        .cloned()
+        .collect::<Vec<_>>();
+    if columns.is_empty() {
+        state.pivot_view.append(&StatusPage::builder()
+            .icon_name("view-list-symbolic")
+            .title("Nothing to Summarize")
+            .description("Relation and rollup columns can't be grouped by")
+            .vexpand(true)
+            .build());
+        return;
+    }
+    let position = |id| columns.iter().position(|c| c.id == id);
+    // Starts out summing the first number column by the first single select one
+    let pivot = state.pivot.borrow().clone()
+        .filter(|p| position(p.group).is_some() && position(p.value).is_some())
+        .unwrap_or_else(|| {
+            let group = columns.iter().find(|c| matches!(c.kind, ColumnKind::Select(_))).unwrap_or(&columns[0]);
+            let number = columns.iter().find(|c| matches!(c.kind, ColumnKind::Number));
+            Pivot {
+                group: group.id,
+                value: number.unwrap_or(&columns[0]).id,
+                aggregate: if number.is_some() { Aggregate::Sum } else { Aggregate::Count },
+            }
+        });
+    *state.pivot.borrow_mut() = Some(pivot.clone());
+
+    let names = columns.iter().map(|c| c.name.as_str()).collect::<Vec<_>>();
+    let group_by = DropDown::from_strings(&names);
+    group_by.set_selected(position(pivot.group).unwrap() as u32);
+    let value = DropDown::from_strings(&names);
+    value.set_selected(position(pivot.value).unwrap() as u32);
+    let aggregate = DropDown::from_strings(&AGGREGATES.iter().map(|&(label, _)| label).collect::<Vec<_>>());
+    aggregate.set_selected(AGGREGATES.iter().position(|&(_, name)| name == pivot.aggregate.name()).unwrap_or(0) as u32);
+    let set_pivot = clone!(@strong state, @strong columns, @strong group_by, @strong value, @strong aggregate => move || {
+        let column = |dropdown: &DropDown| columns.get(dropdown.selected() as usize).map(|c| c.id);
+        let name = AGGREGATES.get(aggregate.selected() as usize).map(|&(_, name)| name);
+        if let (Some(group), Some(value), Some(aggregate)) = (column(&group_by), column(&value), name.and_then(Aggregate::from_name)) {
+            *state.pivot.borrow_mut() = Some(Pivot { group, value, aggregate });
+        }
+        // Not while the drop down is still handling its own change
+        glib::idle_add_local_once(clone!(@strong state => move || update_pivot(&state)));
+    });
+    for dropdown in [&group_by, &value, &aggregate] {
+        dropdown.connect_selected_notify(clone!(@strong set_pivot => move |_| set_pivot()));
+    }
+    let save = Button::builder()
+        .css_classes(["flat"])
+        .label("Save as Table")
+        .action_name("table.save-pivot")
+        .build();
+    let bar = gtk4::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .build();
+    bar.append(&Label::new(Some("Group By")));
+    bar.append(&group_by);
+    bar.append(&aggregate);
+    bar.append(&Label::new(Some("of")));
+    bar.append(&value);
+    bar.append(&gtk4::Box::builder().hexpand(true).build());
+    bar.append(&save);
+    state.pivot_view.append(&bar);
+
+    let summary = match state.make_table_content().pivot(&pivot) {
+        Some(summary) => summary,
+        None => return,
+    };
+    let table = Grid::builder()
+        .css_classes(["table-pivot"])
+        .column_spacing(24)
+        .row_spacing(6)
+        .build();
+    for (i, column) in summary.columns.iter().enumerate() {
+        table.attach(&Label::builder()
+            .css_classes(["heading"])
+            .label(column.name.as_str())
+            .halign(Align::Start)
+            .build(), i as i32, 0, 1, 1);
+    }
+    for (row, cells) in summary.cells.chunks(summary.width()).enumerate() {
+        for (i, cell) in cells.iter().enumerate() {
+            let empty = cell.content.is_none();
+            let label = Label::builder()
+                .label(cell.content.as_deref().unwrap_or(if i == 0 { "No Value" } else { "" }))
+                .halign(if matches!(summary.columns[i].kind, ColumnKind::Number) { Align::End } else { Align::Start })
+                .selectable(true)
+                .build();
+            if empty {
+                label.add_css_class("dim-label");
+            }
+            table.attach(&label, i as i32, row as i32 + 1, 1, 1);
+        }
+    }
+    state.pivot_view.append(&table);
+}
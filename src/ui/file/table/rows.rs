@@ -0,0 +1,196 @@
+use glib::{clone, BoxedAnyObject};
+use gtk4::{prelude::*, gdk::{Rectangle, BUTTON_SECONDARY}, gio::{ActionEntry, Menu, MenuItem, SimpleActionGroup}, GestureClick, NamedAction, PickFlags, PopoverMenu, Shortcut, ShortcutController, ShortcutTrigger, PropagationPhase};
+
+use crate::data::table::row::Row;
+
+use super::{TableEditingState, update_formulas};
+use super::charts::redraw_charts;
+use super::footer::update_footer;
+use super::form::show_form;
+use super::unique::update_duplicates;
+
+/// Row operations of the `table` actions, acting on the selected rows
+const ACTIONS: [(&str, &str, &str, fn(&TableEditingState)); 7] = [
+    ("open-row-form", "Open as Form", "<alt>Return", |state| show_form(state, selected_rows(state).into_iter().next())),
+    ("insert-row-above", "Insert Row Above", "<primary><shift>Return", |state| insert_row(state, false)),
+    ("insert-row-below", "Insert Row Below", "<primary>Return", |state| insert_row(state, true)),
+    ("duplicate-rows", "Duplicate", "<primary>d", duplicate_rows),
+    ("move-rows-up", "Move Up", "<alt>Up", |state| move_rows(state, true)),
+    ("move-rows-down", "Move Down", "<alt>Down", |state| move_rows(state, false)),
+    ("delete-rows", "Delete", "<primary>minus", delete_rows),
+];
+
+/// The row operations, for the context menus of the table and its cells
+pub(super) fn row_menu() -> Menu {
+    let menu = Menu::new();
+    // Separate opening, the insertions, the duplicating and moving, and deleting
+    for actions in [&ACTIONS[..1], &ACTIONS[1..3], &ACTIONS[3..6], &ACTIONS[6..]] {
+        let section = Menu::new();
+        for &(name, label, accel, _) in actions {
+            let item = MenuItem::new(Some(label), Some(format!("table.{name}").as_str()));
+            item.set_attribute_value("accel", Some(&accel.to_variant()));
+            section.append_item(&item);
+        }
+        menu.append_section(None, &section);
+    }
+    menu
+}
+
+/// Adds the row operations to the `table` actions, with their shortcuts and a context menu on the rows
+pub(super) fn add_row_actions(state: &TableEditingState, group: &SimpleActionGroup) {
+    let shortcuts = ShortcutController::new();
+    // Cells would take the keys for themselves otherwise
+    shortcuts.set_propagation_phase(PropagationPhase::Capture);
+    for (name, _, accel, f) in ACTIONS {
+        group.add_action_entries([ActionEntry::builder(name)
+            .activate(clone!(@strong state => move |_: &SimpleActionGroup, _, _| f(&state)))
+            .build()]);
+        shortcuts.add_shortcut(&Shortcut::new(
+            ShortcutTrigger::parse_string(accel),
+            Some(NamedAction::new(&format!("table.{name}"))),
+        ));
+    }
+    state.grid.add_controller(shortcuts);
+
+    let popover = PopoverMenu::from_model(Some(&row_menu()));
+    popover.set_has_arrow(false);
+    popover.set_parent(&state.grid);
+    let click = GestureClick::new();
+    click.set_button(BUTTON_SECONDARY);
+    click.connect_pressed(clone!(@strong state, @strong popover => move |_, _, x, y| {
+        // Right-clicking a row outside the selection acts on that row instead
+        if let Some(row) = state.grid.pick(x, y, PickFlags::DEFAULT).and_then(|picked| row_of(&state, &picked)) {
+            if !selected_rows(&state).contains(&row) {
+                select_rows(&state, &[row]);
+            }
+        }
+        popover.set_pointing_to(Some(&Rectangle::new(x as i32, y as i32, 1, 1)));
+        popover.popup();
+    }));
+    state.grid.add_controller(click);
+}
+
+/// The row a widget in one of the bound cells belongs to
+fn row_of(state: &TableEditingState, widget: &gtk4::Widget) -> Option<BoxedAnyObject> {
+    state.bound.borrow().iter()
+        .find(|(_, _, cell)| cell == widget || widget.is_ancestor(cell))
+        .map(|(_, row, _)| row.clone())
+}
+
+/// The rows to act on, which are the row being edited when it's outside the selection
+fn selected_rows(state: &TableEditingState) -> Vec<BoxedAnyObject> {
+    let model = state.model.borrow();
+    let selected = (0..model.n_items())
+        .filter(|&i| model.is_selected(i))
+        .filter_map(|i| model.item(i).and_downcast::<BoxedAnyObject>())
+        .collect::<Vec<_>>();
+    let focused = state.grid.root()
+        .and_then(|root| root.focus())
+        .and_then(|focus| row_of(state, &focus));
+    match focused {
+        Some(row) if !selected.contains(&row) => vec![row],
+        _ => selected,
+    }
+}
+
+/// Where the rows to act on are stored, in order
+fn selected_indices(state: &TableEditingState) -> Vec<usize> {
+    let rows = selected_rows(state);
+    let mut indices = state.rows.iter::<BoxedAnyObject>()
+        .filter_map(Result::ok)
+        .enumerate()
+        .filter(|(_, row)| rows.contains(row))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    indices.sort_unstable();
+    indices
+}
+
+fn select_rows(state: &TableEditingState, rows: &[BoxedAnyObject]) {
+    let model = state.model.borrow();
+    model.unselect_all();
+    for i in 0..model.n_items() {
+        if model.item(i).and_downcast::<BoxedAnyObject>().map_or(false, |row| rows.contains(&row)) {
+            model.select_item(i, false);
+        }
+    }
+}
+
+/// Updates what depends on the rows after they change
+pub(super) fn rows_changed(state: &TableEditingState) {
+    let n = state.columns.borrow().len();
+    for i in 0..n {
+        update_duplicates(state, i);
+    }
+    update_formulas(state);
+    update_footer(state);
+    redraw_charts(state);
+}
+
+fn row_at(state: &TableEditingState, i: usize) -> BoxedAnyObject {
+    state.rows.item(i as u32).and_downcast::<BoxedAnyObject>().unwrap()
+}
+
+fn insert_row(state: &TableEditingState, below: bool) {
+    let indices = selected_indices(state);
+    let at = match (below, indices.first(), indices.last()) {
+        (false, Some(&first), _) => first,
+        (true, _, Some(&last)) => last + 1,
+        _ => state.rows.n_items() as usize,
+    };
+    let row = BoxedAnyObject::new(Row::create_empty(state.columns.borrow().len()));
+    state.rows.insert(at as u32, &row);
+    rows_changed(state);
+    select_rows(state, &[row]);
+}
+
+/// Puts a copy of each row right after it
+fn duplicate_rows(state: &TableEditingState) {
+    let mut copies = Vec::new();
+    for i in selected_indices(state).into_iter().rev() {
+        let copy = BoxedAnyObject::new(row_at(state, i).borrow::<Row>().duplicate());
+        state.rows.insert(i as u32 + 1, &copy);
+        copies.push(copy);
+    }
+    rows_changed(state);
+    select_rows(state, &copies);
+}
+
+/// Moves the rows by one in the stored order, which is what's shown while the table isn't sorted
+fn move_rows(state: &TableEditingState, up: bool) {
+    let indices = selected_indices(state);
+    let n = state.rows.n_items() as usize;
+    let (first, last) = match (indices.first(), indices.last()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return,
+    };
+    if (up && first == 0) || (!up && last + 1 == n) {
+        return;
+    }
+    let moved = indices.iter().map(|&i| row_at(state, i)).collect::<Vec<_>>();
+    let mut order = state.rows.iter::<BoxedAnyObject>().filter_map(Result::ok).collect::<Vec<_>>();
+    if up {
+        for &i in &indices {
+            order.swap(i - 1, i);
+        }
+    } else {
+        for &i in indices.iter().rev() {
+            order.swap(i, i + 1);
+        }
+    }
+    let start = if up { first - 1 } else { first };
+    let end = if up { last } else { last + 1 };
+    state.rows.splice(start as u32, (end - start + 1) as u32, &order[start..=end]);
+    rows_changed(state);
+    select_rows(state, &moved);
+}
+
+fn delete_rows(state: &TableEditingState) {
+    for i in selected_indices(state).into_iter().rev() {
+        state.rows.remove(i as u32);
+    }
+    if state.rows.n_items() == 0 {
+        state.rows.append(&BoxedAnyObject::new(Row::create_empty(state.columns.borrow().len())));
+    }
+    rows_changed(state);
+}
@@ -0,0 +1,57 @@
+use std::collections::HashSet;
+
+use adw::{prelude::*, Banner};
+use glib::BoxedAnyObject;
+use gtk4::Widget;
+
+use crate::data::table::{duplicate_values, row::Row, TableContent};
+
+use super::TableEditingState;
+
+/// Tells about unique columns that already held duplicates when the table was opened
+pub(super) fn create_unique_banner(content: &TableContent) -> Banner {
+    let violations = content.unique_violations();
+    let columns = violations.iter()
+        .map(|(name, n)| format!("“{}” ({})", name, n))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let banner = Banner::builder()
+        .title(format!("Unique columns with repeated values: {}", columns))
+        .button_label("Dismiss")
+        .revealed(!violations.is_empty())
+        .build();
+    banner.connect_button_clicked(|banner| banner.set_revealed(false));
+    banner
+}
+
+/// Finds the repeated values of the column at `i` if it's unique, and marks its bound cells holding them
+pub(super) fn update_duplicates(state: &TableEditingState, i: usize) {
+    let (id, unique) = {
+        let column = &state.columns.borrow()[i];
+        (column.id, column.unique)
+    };
+    let duplicates = if unique {
+        let rows = state.rows.iter::<BoxedAnyObject>().filter_map(Result::ok).collect::<Vec<_>>();
+        let rows = rows.iter().map(|row| row.borrow::<Row>()).collect::<Vec<_>>();
+        duplicate_values(rows.iter().filter_map(|row| row.cells[i].content.as_deref()))
+    } else {
+        HashSet::new()
+    };
+    for (column, row, widget) in state.bound.borrow().iter() {
+        if *column == id {
+            let row = row.borrow::<Row>();
+            mark_duplicate(widget, row.cells[i].content.as_ref().map_or(false, |c| duplicates.contains(c)));
+        }
+    }
+    state.duplicates.borrow_mut().insert(id, duplicates);
+}
+
+pub(super) fn mark_duplicate(widget: &Widget, duplicate: bool) {
+    if duplicate {
+        widget.add_css_class("duplicate");
+        widget.set_tooltip_text(Some("This value is repeated in a unique column"));
+    } else {
+        widget.remove_css_class("duplicate");
+        widget.set_tooltip_text(None);
+    }
+}
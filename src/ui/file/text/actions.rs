@@ -0,0 +1,97 @@
+use glib::clone;
+use gtk4::{prelude::*, gio::{ActionEntry, Menu, SimpleActionGroup}, NamedAction, Shortcut, ShortcutController, ShortcutTrigger, Widget};
+
+use crate::data::text::RichText;
+
+use super::{TextEditor, TextEditingParagraph};
+use super::components::{create_heading, create_p, replace_paragraph};
+use super::list::update_list_markers;
+use super::marks::read_rich_text;
+
+/// Paragraph kinds the `text` actions convert into, `None` being plain text
+const KINDS: [(&str, Option<usize>, &str, &str); 4] = [
+    ("into-text", None, "Into Text", "<primary>1"),
+    ("into-heading2", Some(2), "Into Heading 2", "<primary>2"),
+    ("into-heading3", Some(3), "Into Heading 3", "<primary>3"),
+    ("into-heading4", Some(4), "Into Heading 4", "<primary>4"),
+];
+
+pub(super) fn text_menu() -> Menu {
+    let menu = Menu::new();
+    for (name, _, label, _) in KINDS {
+        menu.append(Some(label), Some(format!("text.{name}").as_str()));
+    }
+    menu
+}
+
+/// Gives the paragraph its own `text` action group, so the context menu and shortcuts convert this one
+pub(super) fn add_paragraph_actions(editor: &TextEditor, me: &impl IsA<Widget>) {
+    let me: Widget = me.clone().upcast();
+    let group = SimpleActionGroup::new();
+    let shortcuts = ShortcutController::new();
+    for (name, level, _, accel) in KINDS {
+        group.add_action_entries([ActionEntry::builder(name)
+            .activate(clone!(@strong editor, @strong me => move |_: &SimpleActionGroup, _, _| {
+                convert_paragraph(&editor, &me, level);
+            }))
+            .build()]);
+        shortcuts.add_shortcut(&Shortcut::new(
+            ShortcutTrigger::parse_string(accel),
+            Some(NamedAction::new(&format!("text.{name}"))),
+        ));
+    }
+    me.insert_action_group("text", Some(&group));
+    me.add_controller(shortcuts);
+}
+
+/// Replaces the paragraph `me` belongs to with a heading of `level`, or text, keeping the cursor where it was
+fn convert_paragraph(editor: &TextEditor, me: &Widget, level: Option<usize>) {
+    let mut paragraphs = editor.content.borrow_mut();
+    let i = match paragraphs.iter().position(|p| {
+        p.widget() == *me || p.text_view().map_or(false, |v| v.upcast_ref::<Widget>() == me)
+    }) {
+        Some(i) => i,
+        None => return,
+    };
+    let (text, cursor) = match &paragraphs[i] {
+        TextEditingParagraph::Text(_) if level.is_none() => return,
+        TextEditingParagraph::H2(_) if level == Some(2) => return,
+        TextEditingParagraph::H3(_) if level == Some(3) => return,
+        TextEditingParagraph::H4(_) if level == Some(4) => return,
+        TextEditingParagraph::H2(t) | TextEditingParagraph::H3(t) | TextEditingParagraph::H4(t) => {
+            (RichText::plain(t.text().to_string()), t.position())
+        },
+        p => match p.text_view() {
+            Some(v) => {
+                let b = v.buffer();
+                (read_rich_text(&b, &b.start_iter(), &b.end_iter()), b.cursor_position())
+            },
+            None => return,
+        },
+    };
+    let p = match level {
+        Some(level) => {
+            // Headings are a single line of plain text
+            let heading = create_heading(editor, level, text.text.replace('\n', " "));
+            match level {
+                2 => TextEditingParagraph::H2(heading),
+                3 => TextEditingParagraph::H3(heading),
+                _ => TextEditingParagraph::H4(heading),
+            }
+        },
+        None => TextEditingParagraph::Text(create_p(editor, text)),
+    };
+    replace_paragraph(&mut paragraphs, &editor.view, i, p);
+    update_list_markers(&paragraphs);
+    match &paragraphs[i] {
+        TextEditingParagraph::H2(t) | TextEditingParagraph::H3(t) | TextEditingParagraph::H4(t) => {
+            t.grab_focus();
+            t.set_position(cursor);
+        },
+        p => if let Some(v) = p.text_view() {
+            v.grab_focus();
+            let b = v.buffer();
+            b.place_cursor(&b.iter_at_offset(cursor));
+        },
+    }
+}
@@ -0,0 +1,213 @@
+use glib::clone;
+use gtk4::{prelude::*, gdk::{DragAction, FileList, Key, Texture}, gio::{self, Cancellable}, Align, Button, ContentFit, DropTarget, EventControllerKey, GestureClick, Image, Inhibit, Label, Orientation, Picture, TextView, Widget};
+
+use crate::data::text::{Attachment, RichText};
+
+use super::{TextEditor, TextEditingParagraph};
+use super::components::{create_p, focus_first_line, focus_last_line, insert_paragraph};
+use super::list::update_list_markers;
+use super::marks::open_link;
+
+const IMAGE_EXTENSIONS: [&str; 8] = ["png", "jpg", "jpeg", "gif", "webp", "svg", "bmp", "avif"];
+
+#[derive(Debug, Clone)]
+pub(super) struct AssetEditor {
+    pub row: gtk4::Box,
+    path: String,
+    name: String,
+}
+
+impl AssetEditor {
+    pub fn attachment(&self) -> Attachment {
+        Attachment {
+            path: self.path.clone(),
+            name: self.name.clone(),
+        }
+    }
+}
+
+fn is_image(path: &str) -> bool {
+    path.rsplit_once('.')
+        .map_or(false, |(_, extension)| IMAGE_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+fn create_asset_row(editor: &TextEditor, kind: &str) -> gtk4::Box {
+    let row = gtk4::Box::builder()
+        .css_classes(["asset", kind])
+        .orientation(Orientation::Horizontal)
+        .focusable(true)
+        .hexpand(true)
+        .build();
+    let click = GestureClick::new();
+    click.connect_pressed(clone!(@strong row => move |_, _, _, _| {
+        row.grab_focus();
+    }));
+    row.add_controller(click);
+    row.add_controller(create_asset_controller(editor, &row));
+    row
+}
+
+pub(super) fn create_image(editor: &TextEditor, attachment: Attachment) -> AssetEditor {
+    let file = format!("{}/{}", editor.dir, attachment.path);
+    let picture = Picture::builder()
+        .file(&gio::File::for_path(file))
+        .alternative_text(&attachment.name)
+        .can_shrink(true)
+        .content_fit(ContentFit::ScaleDown)
+        .halign(Align::Start)
+        .build();
+    let row = create_asset_row(editor, "image");
+    row.append(&picture);
+    AssetEditor { row, path: attachment.path, name: attachment.name }
+}
+
+pub(super) fn create_attachment(editor: &TextEditor, attachment: Attachment) -> AssetEditor {
+    let file = format!("{}/{}", editor.dir, attachment.path);
+    let chip = gtk4::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .build();
+    chip.append(&Image::from_icon_name("mail-attachment-symbolic"));
+    chip.append(&Label::new(Some(&attachment.name)));
+    let button = Button::builder()
+        .css_classes(["attachment-chip"])
+        .halign(Align::Start)
+        .child(&chip)
+        .build();
+    button.connect_clicked(move |button| {
+        open_link(button, &gio::File::for_path(&file).uri());
+    });
+    let row = create_asset_row(editor, "attachment");
+    row.append(&button);
+    AssetEditor { row, path: attachment.path, name: attachment.name }
+}
+
+/// Copies the files into the text file's assets and adds a paragraph for each after the one at `after`
+pub(super) fn insert_files(editor: &TextEditor, after: usize, files: Vec<gio::File>) {
+    let mut after = after;
+    for file in files {
+        let path = match file.path() {
+            Some(source) => editor.db.lock().unwrap().import_asset(editor.id, &source),
+            None => None,
+        };
+        if let Some(path) = path {
+            let name = file.basename()
+                .and_then(|n| n.to_str().map(|n| n.to_string()))
+                .unwrap_or_else(|| path.clone());
+            let attachment = Attachment { path, name };
+            let p = if is_image(&attachment.path) {
+                TextEditingParagraph::Image(create_image(editor, attachment))
+            } else {
+                TextEditingParagraph::Attachment(create_attachment(editor, attachment))
+            };
+            let mut paragraphs = editor.content.borrow_mut();
+            insert_paragraph(&mut paragraphs, &editor.view, after, p);
+            update_list_markers(&paragraphs);
+            after += 1;
+        }
+    }
+}
+
+pub(super) fn insert_texture(editor: &TextEditor, after: usize, texture: Texture) {
+    let (path, file) = {
+        let db = editor.db.lock().unwrap();
+        let path = db.new_asset_path(editor.id, "Pasted Image.png");
+        let file = db.asset_path(editor.id, &path);
+        (path, file)
+    };
+    if texture.save_to_png(file).is_ok() {
+        let image = create_image(editor, Attachment { path, name: "Pasted Image".to_string() });
+        let mut paragraphs = editor.content.borrow_mut();
+        insert_paragraph(&mut paragraphs, &editor.view, after, TextEditingParagraph::Image(image));
+        update_list_markers(&paragraphs);
+    }
+}
+
+/// Pastes files or images from the clipboard as paragraphs after `v`'s,
+/// returns false if the clipboard has neither
+pub(super) fn paste_assets(editor: &TextEditor, v: &TextView) -> bool {
+    let clipboard = v.clipboard();
+    let formats = clipboard.formats();
+    let after = clone!(@strong editor, @strong v => move || {
+        editor.content.borrow().iter().position(|p| p.text_view() == Some(&v)).unwrap()
+    });
+    if formats.contain_mime_type("text/uri-list") {
+        clipboard.read_value_async(FileList::static_type(), glib::PRIORITY_DEFAULT, None::<&Cancellable>, clone!(@strong editor => move |value| {
+            if let Some(files) = value.ok().and_then(|v| v.get::<FileList>().ok()) {
+                insert_files(&editor, after(), files.files());
+            }
+        }));
+        true
+    } else if formats.contain_mime_type("image/png") {
+        clipboard.read_texture_async(None::<&Cancellable>, clone!(@strong editor => move |texture| {
+            if let Ok(Some(texture)) = texture {
+                insert_texture(&editor, after(), texture);
+            }
+        }));
+        true
+    } else {
+        false
+    }
+}
+
+/// Accepts files dragged onto `widget`, dropping them after the paragraph under the pointer
+pub(super) fn create_drop_target(editor: &TextEditor, widget: &impl IsA<Widget>) -> DropTarget {
+    let widget: Widget = widget.clone().upcast();
+    let target = DropTarget::new(FileList::static_type(), DragAction::COPY);
+    target.connect_drop(clone!(@strong editor, @strong widget => move |_, value, x, y| {
+        let files = match value.get::<FileList>() {
+            Ok(files) => files,
+            Err(_) => return false,
+        };
+        let y = widget.translate_coordinates(&editor.view, x, y).map_or(y, |(_, y)| y);
+        let mut after = 0;
+        for (i, p) in editor.content.borrow().iter().enumerate() {
+            if let Some(bounds) = p.widget().compute_bounds(&editor.view) {
+                if bounds.y() as f64 <= y {
+                    after = i;
+                }
+            }
+        }
+        insert_files(&editor, after, files.files());
+        true
+    }));
+    target
+}
+
+fn create_asset_controller(editor: &TextEditor, row: &gtk4::Box) -> EventControllerKey {
+    let controller = EventControllerKey::new();
+    controller.connect_key_pressed(clone!(@strong editor, @strong row => move |_, k, _, _| {
+        let mut paragraphs = editor.content.borrow_mut();
+        let i = paragraphs.iter().position(|p| p.widget() == row.clone().upcast::<Widget>()).unwrap();
+        match k {
+            Key::Up if i > 0 => {
+                focus_last_line(&paragraphs[i - 1]);
+                Inhibit(true)
+            },
+            Key::Down if i + 1 < paragraphs.len() => {
+                focus_first_line(&paragraphs[i + 1]);
+                Inhibit(true)
+            },
+            Key::Return => {
+                let v = create_p(&editor, RichText::default());
+                insert_paragraph(&mut paragraphs, &editor.view, i, TextEditingParagraph::Text(v));
+                focus_first_line(&paragraphs[i + 1]);
+                update_list_markers(&paragraphs);
+                Inhibit(true)
+            },
+            Key::BackSpace | Key::Delete => {
+                editor.view.remove(&row);
+                paragraphs.remove(i);
+                if paragraphs.is_empty() {
+                    let v = create_p(&editor, RichText::default());
+                    editor.view.append(&v);
+                    paragraphs.push(TextEditingParagraph::Text(v));
+                }
+                focus_last_line(&paragraphs[i.saturating_sub(1)]);
+                update_list_markers(&paragraphs);
+                Inhibit(true)
+            },
+            _ => Inhibit(false),
+        }
+    }));
+    controller
+}
@@ -0,0 +1,40 @@
+use glib::clone;
+use gtk4::{traits::{BoxExt, CheckButtonExt, WidgetExt}, Align, CheckButton, Orientation, TextView};
+
+use crate::data::text::RichText;
+
+use super::TextEditor;
+use super::components::create_p;
+
+#[derive(Debug, Clone)]
+pub(super) struct CheckItemEditor {
+    pub row: gtk4::Box,
+    pub check: CheckButton,
+    pub text: TextView,
+}
+
+pub(super) fn create_check_item(editor: &TextEditor, checked: bool, text: RichText) -> CheckItemEditor {
+    let text = create_p(editor, text);
+    let check = CheckButton::builder()
+        .active(checked)
+        .valign(Align::Start)
+        .build();
+    let row = gtk4::Box::builder()
+        .css_classes(["check-item"])
+        .orientation(Orientation::Horizontal)
+        .hexpand(true)
+        .build();
+    if checked {
+        row.add_css_class("checked");
+    }
+    check.connect_toggled(clone!(@strong row => move |check| {
+        if check.is_active() {
+            row.add_css_class("checked");
+        } else {
+            row.remove_css_class("checked");
+        }
+    }));
+    row.append(&check);
+    row.append(&text);
+    CheckItemEditor { row, check, text }
+}
@@ -0,0 +1,78 @@
+use glib::clone;
+use gtk4::{traits::{BoxExt, EditableExt, TextBufferExt, WidgetExt}, pango::Style, Align, Orientation, Text, TextBuffer, TextTag, TextView};
+
+use super::TextEditor;
+use super::components::create_text_controller;
+use super::highlight::{highlight, Token};
+
+#[derive(Debug, Clone)]
+pub(super) struct CodeBlockEditor {
+    pub row: gtk4::Box,
+    pub language: Text,
+    pub text: TextView,
+}
+
+impl CodeBlockEditor {
+    pub fn language(&self) -> Option<String> {
+        let language = self.language.text().trim().to_string();
+        if language.is_empty() {
+            None
+        } else {
+            Some(language)
+        }
+    }
+}
+
+pub(super) fn create_code_block(editor: &TextEditor, language: Option<String>, code: String) -> CodeBlockEditor {
+    let buffer = TextBuffer::new(None);
+    let tags = buffer.tag_table();
+    tags.add(&TextTag::builder().name(Token::Keyword.tag_name()).foreground("#9141ac").weight(700).build());
+    tags.add(&TextTag::builder().name(Token::String.tag_name()).foreground("#26a269").build());
+    tags.add(&TextTag::builder().name(Token::Comment.tag_name()).foreground("#77767b").style(Style::Italic).build());
+    tags.add(&TextTag::builder().name(Token::Number.tag_name()).foreground("#c64600").build());
+    buffer.begin_irreversible_action();
+    buffer.set_text(&code);
+    buffer.end_irreversible_action();
+
+    let text = TextView::builder()
+        .buffer(&buffer)
+        .monospace(true)
+        .hexpand(true)
+        .build();
+    text.add_controller(create_text_controller(editor, &text));
+
+    let language = Text::builder()
+        .css_classes(["code-language"])
+        .placeholder_text("Language")
+        .halign(Align::End)
+        .editable(true)
+        .text(language.unwrap_or_default())
+        .build();
+    buffer.connect_changed(clone!(@strong language => move |buffer| {
+        highlight_buffer(buffer, &language.text());
+    }));
+    language.connect_text_notify(clone!(@strong buffer => move |language| {
+        highlight_buffer(&buffer, &language.text());
+    }));
+    highlight_buffer(&buffer, &language.text());
+
+    let row = gtk4::Box::builder()
+        .css_classes(["code-block"])
+        .orientation(Orientation::Vertical)
+        .hexpand(true)
+        .build();
+    row.append(&language);
+    row.append(&text);
+    CodeBlockEditor { row, language, text }
+}
+
+fn highlight_buffer(buffer: &TextBuffer, language: &str) {
+    let (start, end) = buffer.bounds();
+    for token in Token::ALL {
+        buffer.remove_tag_by_name(token.tag_name(), &start, &end);
+    }
+    let code = buffer.text(&start, &end, true);
+    for (s, e, token) in highlight(language, &code) {
+        buffer.apply_tag_by_name(token.tag_name(), &buffer.iter_at_offset(s as i32), &buffer.iter_at_offset(e as i32));
+    }
+}
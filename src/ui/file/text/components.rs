@@ -1,151 +1,341 @@
-use std::{cell::RefCell, rc::Rc};
+use glib::{clone, ObjectExt};
+use gtk4::{prelude::*, traits::{BoxExt, WidgetExt, TextBufferExt, TextViewExt, EditableExt, GestureClickExt}, TextView, EventControllerKey, gdk::{Key, ModifierType}, Inhibit, Text, GestureClick, gio::Cancellable};
 
-use glib::clone;
-use gtk4::{traits::{BoxExt, WidgetExt, TextBufferExt, TextViewExt, EditableExt}, TextBuffer, TextView, EventControllerKey, gdk::Key, Inhibit, Text};
+use crate::data::text::{RichText, MarkKind, ListKind};
 
-use super::TextEditingParagraph;
+use super::{TextEditor, TextEditingParagraph};
+use super::actions::{add_paragraph_actions, text_menu};
+use super::asset::{create_drop_target, paste_assets};
+use super::check::create_check_item;
+use super::code::create_code_block;
+use super::list::{create_list_item, max_indent, update_list_markers};
+use crate::ui::cmd::search::file_picker_window;
 
-pub(super) fn create_heading(edit_content: &Rc<RefCell<Vec<TextEditingParagraph>>>, view: &gtk4::Box, level: usize, text: String) -> Text {
-    Text::builder()
+use super::marks::{create_buffer, insert_rich_text, read_rich_text, toggle_mark, paste_over_selection, link_at, follow_link, insert_link};
+
+pub(super) fn create_heading(editor: &TextEditor, level: usize, text: String) -> Text {
+    let t = Text::builder()
         .css_classes([format!("title-{level}").as_str()])
         .placeholder_text(format!("Heading {level}"))
         .hexpand(true)
         .editable(true)
         .text(&text)
-        //.extra_menu(text_menu)
-        .build()
+        .extra_menu(&text_menu())
+        .build();
+    add_paragraph_actions(editor, &t);
+    t
 }
 
-pub(super) fn create_p(edit_content: &Rc<RefCell<Vec<TextEditingParagraph>>>, view: &gtk4::Box, text: String) -> TextView {
-    let b = TextBuffer::builder().text(text).build();
+pub(super) fn create_p(editor: &TextEditor, text: RichText) -> TextView {
+    let b = create_buffer(&text);
     let v = TextView::builder()
         .buffer(&b)
         .hexpand(true)
-        //.extra_menu(text_menu)
+        .extra_menu(&text_menu())
         .build();
-    v.connect_paste_clipboard(|v| {
-        
-    });
-    v.add_controller(create_text_controller(view, &v, edit_content));
+    v.connect_paste_clipboard(clone!(@strong editor => move |v| {
+        let buffer = v.buffer();
+        if paste_assets(&editor, v) {
+            v.stop_signal_emission_by_name("paste-clipboard");
+        } else if buffer.has_selection() {
+            v.stop_signal_emission_by_name("paste-clipboard");
+            v.clipboard().read_text_async(None::<&Cancellable>, move |text| {
+                if let Ok(Some(text)) = text {
+                    paste_over_selection(&buffer, &text);
+                }
+            });
+        }
+    }));
+    let click = GestureClick::new();
+    click.connect_released(clone!(@strong v => move |_, n, x, y| {
+        if n == 1 && !v.buffer().has_selection() {
+            if let Some(link) = link_at(&v, x, y) {
+                follow_link(&v, &link);
+            }
+        }
+    }));
+    v.add_controller(click);
+    v.add_controller(create_drop_target(editor, &v));
+    v.add_controller(create_text_controller(editor, &v));
+    add_paragraph_actions(editor, &v);
     v
 }
 
-pub(super) fn create_text_controller(view: &gtk4::Box, me: &TextView, edit_content: &Rc<RefCell<Vec<TextEditingParagraph>>>) -> EventControllerKey {
+/// Puts `p` where the paragraph at `i` was, both in the view and in the editing state
+pub(super) fn replace_paragraph(paragraphs: &mut Vec<TextEditingParagraph>, view: &gtk4::Box, i: usize, p: TextEditingParagraph) {
+    let old = paragraphs[i].widget();
+    view.insert_child_after(&p.widget(), Some(&old));
+    view.remove(&old);
+    paragraphs[i] = p;
+}
+
+/// Adds `p` after the paragraph at `i`
+pub(super) fn insert_paragraph(paragraphs: &mut Vec<TextEditingParagraph>, view: &gtk4::Box, i: usize, p: TextEditingParagraph) {
+    view.insert_child_after(&p.widget(), Some(&paragraphs[i].widget()));
+    paragraphs.insert(i + 1, p);
+}
+
+pub(super) fn focus_last_line(p: &TextEditingParagraph) {
+    match p.text_view() {
+        Some(v) => {
+            v.grab_focus();
+            let b = v.buffer();
+            b.place_cursor(&b.iter_at_line(b.line_count() - 1).unwrap());
+        },
+        None => focus_widget(p),
+    }
+}
+
+pub(super) fn focus_first_line(p: &TextEditingParagraph) {
+    match p.text_view() {
+        Some(v) => {
+            v.grab_focus();
+            let b = v.buffer();
+            b.place_cursor(&b.iter_at_line(0).unwrap());
+        },
+        None => focus_widget(p),
+    }
+}
+
+fn focus_widget(p: &TextEditingParagraph) {
+    match p {
+        TextEditingParagraph::H2(t) | TextEditingParagraph::H3(t) | TextEditingParagraph::H4(t) => {
+            t.grab_focus();
+            t.set_position(0);
+        },
+        _ => {
+            p.widget().grab_focus();
+        },
+    }
+}
+
+fn position_of(paragraphs: &[TextEditingParagraph], me: &TextView) -> usize {
+    paragraphs.iter().position(|p| p.text_view() == Some(me)).unwrap()
+}
+
+/// Lets the user pick a file to replace the `[[` being typed with a link to it
+fn pick_file_link(editor: &TextEditor, me: &TextView) {
+    let app = me.root()
+        .and_downcast::<gtk4::Window>()
+        .and_then(|w| w.application())
+        .and_downcast::<adw::Application>();
+    if let Some(app) = app {
+        file_picker_window(&editor.db, &app, clone!(@strong editor, @strong me => move |id| {
+            let title = match editor.db.lock().unwrap().get_file(id) {
+                Some(display) => display.title.clone(),
+                None => return,
+            };
+            let buffer = me.buffer();
+            let c = buffer.cursor_position();
+            if c >= 2 && buffer.text(&buffer.iter_at_offset(c - 2), &buffer.iter_at_offset(c), true) == "[[" {
+                buffer.delete(&mut buffer.iter_at_offset(c - 2), &mut buffer.iter_at_offset(c));
+            }
+            insert_link(&buffer, &title, MarkKind::FileLink(id));
+            me.grab_focus();
+        }));
+    }
+}
+
+pub(super) fn create_text_controller(editor: &TextEditor, me: &TextView) -> EventControllerKey {
     let controller = EventControllerKey::new();
-    controller.connect_key_pressed(clone!(@strong view, @strong me, @strong edit_content => move |_, k, _, _| {
+    controller.connect_key_pressed(clone!(@strong editor, @strong me => move |_, k, _, modifier| {
         let buffer = me.buffer();
         let c = buffer.cursor_position();
         match k {
+            _ if modifier.contains(ModifierType::CONTROL_MASK) => {
+                let mut paragraphs = editor.content.borrow_mut();
+                let i = position_of(&paragraphs, &me);
+                let is_code = matches!(&paragraphs[i], TextEditingParagraph::Code(_));
+                match k.to_lower() {
+                    // Enter adds lines to a code block, so leaving it takes Ctrl+Enter
+                    Key::Return if is_code => {
+                        let v = create_p(&editor, RichText::default());
+                        insert_paragraph(&mut paragraphs, &editor.view, i, TextEditingParagraph::Text(v));
+                        focus_first_line(&paragraphs[i + 1]);
+                        Inhibit(true)
+                    },
+                    _ if is_code => Inhibit(false),
+                    Key::b => {
+                        toggle_mark(&buffer, MarkKind::Bold);
+                        Inhibit(true)
+                    },
+                    Key::i => {
+                        toggle_mark(&buffer, MarkKind::Italic);
+                        Inhibit(true)
+                    },
+                    Key::e => {
+                        toggle_mark(&buffer, MarkKind::Code);
+                        Inhibit(true)
+                    },
+                    Key::x if modifier.contains(ModifierType::SHIFT_MASK) => {
+                        toggle_mark(&buffer, MarkKind::Strikethrough);
+                        Inhibit(true)
+                    },
+                    _ => Inhibit(false),
+                }
+            },
             Key::Up if buffer.iter_at_offset(c).line() == 0 => {
-                let edit_content = edit_content.borrow_mut();
-                let i = edit_content.iter().position(|p| match p {
-                    TextEditingParagraph::Text(p) => p == &me,
-                    _ => false,
-                }).unwrap();
+                let paragraphs = editor.content.borrow_mut();
+                let i = position_of(&paragraphs, &me);
                 if i > 0 {
-                    match &edit_content[i - 1] {
-                        TextEditingParagraph::Text(b) => {
-                            b.grab_focus();
-                            let b = b.buffer();
-                            b.place_cursor(&b.iter_at_line(b.line_count() - 1).unwrap());
-                            return Inhibit(true);
-                        },
-                        TextEditingParagraph::H2(t) => {
-                            t.grab_focus();
-                            t.set_position(0);
-                            return Inhibit(true);
-                        }
-                        TextEditingParagraph::H3(t) => {
-                            t.grab_focus();
-                            t.set_position(0);
-                            return Inhibit(true);
-                        }
-                        TextEditingParagraph::H4(t) => {
-                            t.grab_focus();
-                            t.set_position(0);
-                            return Inhibit(true);
-                        }
-                    }
+                    focus_last_line(&paragraphs[i - 1]);
+                    return Inhibit(true);
                 }
                 Inhibit(false)
             },
             Key::Down if buffer.iter_at_offset(c).line() == buffer.line_count() - 1 => {
-                let edit_content = edit_content.borrow_mut();
-                let i = edit_content.iter().position(|p| match p {
-                    TextEditingParagraph::Text(p) => p == &me,
-                    _ => false,
-                }).unwrap();
-                if i + 1 < edit_content.len() {
-                    match &edit_content[i + 1] {
-                        TextEditingParagraph::Text(b) => {
-                            b.grab_focus();
-                            let b = b.buffer();
-                            b.place_cursor(&b.iter_at_line(0).unwrap());
-                            return Inhibit(true);
-                        },
-                        TextEditingParagraph::H2(t) => {
-                            t.grab_focus();
-                            t.set_position(0);
-                            return Inhibit(true);
-                        }
-                        TextEditingParagraph::H3(t) => {
-                            t.grab_focus();
-                            t.set_position(0);
-                            return Inhibit(true);
-                        }
-                        TextEditingParagraph::H4(t) => {
-                            t.grab_focus();
-                            t.set_position(0);
-                            return Inhibit(true);
-                        }
+                let paragraphs = editor.content.borrow_mut();
+                let i = position_of(&paragraphs, &me);
+                if i + 1 < paragraphs.len() {
+                    focus_first_line(&paragraphs[i + 1]);
+                    return Inhibit(true);
+                }
+                Inhibit(false)
+            },
+            Key::bracketleft if c > 0 && buffer.text(&buffer.iter_at_offset(c - 1), &buffer.iter_at_offset(c), true) == "[" => {
+                let paragraphs = editor.content.borrow();
+                if !matches!(&paragraphs[position_of(&paragraphs, &me)], TextEditingParagraph::Code(_)) {
+                    pick_file_link(&editor, &me);
+                }
+                Inhibit(false)
+            },
+            Key::space => {
+                let mut paragraphs = editor.content.borrow_mut();
+                let i = position_of(&paragraphs, &me);
+                let prefix = buffer.text(&buffer.start_iter(), &buffer.iter_at_offset(c), true);
+                if !matches!(&paragraphs[i], TextEditingParagraph::Text(_)) {
+                    return Inhibit(false);
+                }
+                let text = read_rich_text(&buffer, &buffer.iter_at_offset(c), &buffer.end_iter());
+                let p = match prefix.as_str() {
+                    "-" | "*" => TextEditingParagraph::ListItem(create_list_item(&editor, ListKind::Bullet, 0, text)),
+                    "1." => TextEditingParagraph::ListItem(create_list_item(&editor, ListKind::Numbered, 0, text)),
+                    "[]" | "[ ]" => TextEditingParagraph::Check(create_check_item(&editor, false, text)),
+                    "[x]" => TextEditingParagraph::Check(create_check_item(&editor, true, text)),
+                    _ => return Inhibit(false),
+                };
+                replace_paragraph(&mut paragraphs, &editor.view, i, p);
+                focus_first_line(&paragraphs[i]);
+                update_list_markers(&paragraphs);
+                Inhibit(true)
+            },
+            Key::Tab | Key::ISO_Left_Tab => {
+                let paragraphs = editor.content.borrow_mut();
+                let i = position_of(&paragraphs, &me);
+                if let TextEditingParagraph::ListItem(item) = &paragraphs[i] {
+                    if k == Key::Tab {
+                        item.set_indent((item.indent() + 1).min(max_indent(&paragraphs, i)));
+                    } else if item.indent() > 0 {
+                        item.set_indent(item.indent() - 1);
                     }
+                    update_list_markers(&paragraphs);
+                    return Inhibit(true);
                 }
                 Inhibit(false)
             },
             Key::Return => {
-                let new = buffer.text(&buffer.iter_at_offset(c), &buffer.end_iter(), true);
+                let mut paragraphs = editor.content.borrow_mut();
+                let i = position_of(&paragraphs, &me);
+                match &paragraphs[i] {
+                    TextEditingParagraph::Code(_) => return Inhibit(false),
+                    TextEditingParagraph::Text(_) => {
+                        // "```lang" on its own opens a code block
+                        let line = buffer.text(&buffer.start_iter(), &buffer.end_iter(), true);
+                        if let Some(language) = line.strip_prefix("```").filter(|l| !l.contains('\n')) {
+                            let language = Some(language.trim().to_string()).filter(|l| !l.is_empty());
+                            let block = create_code_block(&editor, language, String::new());
+                            replace_paragraph(&mut paragraphs, &editor.view, i, TextEditingParagraph::Code(block));
+                            focus_first_line(&paragraphs[i]);
+                            return Inhibit(true);
+                        }
+                    },
+                    _ => (),
+                }
+                if let TextEditingParagraph::ListItem(item) = &paragraphs[i] {
+                    // Enter on an empty item steps out of the list, one level at a time
+                    if buffer.char_count() == 0 {
+                        if item.indent() > 0 {
+                            item.set_indent(item.indent() - 1);
+                        } else {
+                            let v = create_p(&editor, RichText::default());
+                            replace_paragraph(&mut paragraphs, &editor.view, i, TextEditingParagraph::Text(v));
+                            focus_first_line(&paragraphs[i]);
+                        }
+                        update_list_markers(&paragraphs);
+                        return Inhibit(true);
+                    }
+                }
+                if let TextEditingParagraph::Check(_) = &paragraphs[i] {
+                    if buffer.char_count() == 0 {
+                        let v = create_p(&editor, RichText::default());
+                        replace_paragraph(&mut paragraphs, &editor.view, i, TextEditingParagraph::Text(v));
+                        focus_first_line(&paragraphs[i]);
+                        return Inhibit(true);
+                    }
+                }
+
+                let new = read_rich_text(&buffer, &buffer.iter_at_offset(c), &buffer.end_iter());
                 buffer.delete(&mut buffer.iter_at_offset(c), &mut buffer.end_iter());
-                let v = create_p(&edit_content, &view, new.to_string());
-
-                let mut edit_content = edit_content.borrow_mut();
-                let i = edit_content.iter().position(|p| match p {
-                    TextEditingParagraph::Text(p) => p == &me,
-                    _ => false,
-                }).unwrap();
-
-                view.insert_child_after(&v, Some(&me));
-                v.grab_focus();
-                v.buffer().place_cursor(&v.buffer().start_iter());
-                edit_content.insert(i + 1, TextEditingParagraph::Text(v));
-                
+                let p = match &paragraphs[i] {
+                    TextEditingParagraph::ListItem(item) =>
+                        TextEditingParagraph::ListItem(create_list_item(&editor, item.kind, item.indent(), new)),
+                    TextEditingParagraph::Check(_) =>
+                        TextEditingParagraph::Check(create_check_item(&editor, false, new)),
+                    _ => TextEditingParagraph::Text(create_p(&editor, new)),
+                };
+
+                insert_paragraph(&mut paragraphs, &editor.view, i, p);
+                focus_first_line(&paragraphs[i + 1]);
+                update_list_markers(&paragraphs);
+
                 Inhibit(true)
             },
             Key::BackSpace if c == 0 => {
-                let mut edit_content = edit_content.borrow_mut();
-                let i = edit_content.iter().position(|p| match p {
-                    TextEditingParagraph::Text(p) => p == &me,
-                    _ => false,
-                }).unwrap();
-
-                let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), true);
-
-                match &edit_content[i - 1] {
-                    TextEditingParagraph::Text(b) => {
-                        b.grab_focus();
-                        let b = b.buffer();
-                        b.place_cursor(&b.end_iter());
-                        b.insert(&mut b.end_iter(), &text);
-                        view.remove(&me);
-                        edit_content.remove(i);
-                        return Inhibit(true);
-                    },
-                    _ => (),
+                let mut paragraphs = editor.content.borrow_mut();
+                let i = position_of(&paragraphs, &me);
+
+                if let TextEditingParagraph::Code(_) = &paragraphs[i] {
+                    if buffer.char_count() > 0 {
+                        return Inhibit(false);
+                    }
+                    let v = create_p(&editor, RichText::default());
+                    replace_paragraph(&mut paragraphs, &editor.view, i, TextEditingParagraph::Text(v));
+                    focus_first_line(&paragraphs[i]);
+                    return Inhibit(true);
+                }
+
+                let text = read_rich_text(&buffer, &buffer.start_iter(), &buffer.end_iter());
+
+                // Backspace in an empty item ends the list there, other items are merged into the one before
+                let is_item = matches!(&paragraphs[i], TextEditingParagraph::ListItem(_) | TextEditingParagraph::Check(_));
+                if is_item && text.text.is_empty() {
+                    let v = create_p(&editor, text);
+                    replace_paragraph(&mut paragraphs, &editor.view, i, TextEditingParagraph::Text(v));
+                    focus_first_line(&paragraphs[i]);
+                    update_list_markers(&paragraphs);
+                    return Inhibit(true);
                 }
-                
+
+                if let Some(b) = i.checked_sub(1).and_then(|i| paragraphs[i].text_view()) {
+                    // Code blocks hold plain text only
+                    let text = match &paragraphs[i - 1] {
+                        TextEditingParagraph::Code(_) => RichText::plain(text.text),
+                        _ => text,
+                    };
+                    b.grab_focus();
+                    let b = b.buffer();
+                    let end = b.end_iter().offset();
+                    insert_rich_text(&b, end, &text);
+                    b.place_cursor(&b.iter_at_offset(end));
+                    editor.view.remove(&paragraphs[i].widget());
+                    paragraphs.remove(i);
+                    update_list_markers(&paragraphs);
+                }
+
                 Inhibit(true)
             },
             _ => Inhibit(false),
         }
     }));
     controller
-}
\ No newline at end of file
+}
@@ -0,0 +1,148 @@
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Token {
+    Keyword,
+    String,
+    Comment,
+    Number,
+}
+
+impl Token {
+    pub const ALL: [Token; 4] = [Token::Keyword, Token::String, Token::Comment, Token::Number];
+
+    pub fn tag_name(&self) -> &'static str {
+        match self {
+            Token::Keyword => "keyword",
+            Token::String => "string",
+            Token::Comment => "comment",
+            Token::Number => "number",
+        }
+    }
+}
+
+struct Syntax {
+    keywords: &'static [&'static str],
+    line_comment: Option<&'static str>,
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+}
+
+const RUST: Syntax = Syntax {
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+        "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+        "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe",
+        "use", "where", "while",
+    ],
+    line_comment: Some("//"),
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"'],
+};
+
+const PYTHON: Syntax = Syntax {
+    keywords: &[
+        "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+        "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in",
+        "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+    ],
+    line_comment: Some("#"),
+    block_comment: None,
+    quotes: &['"', '\''],
+};
+
+const JSON: Syntax = Syntax {
+    keywords: &["true", "false", "null"],
+    line_comment: None,
+    block_comment: None,
+    quotes: &['"'],
+};
+
+const SHELL: Syntax = Syntax {
+    keywords: &[
+        "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if", "in",
+        "local", "return", "then", "until", "while",
+    ],
+    line_comment: Some("#"),
+    block_comment: None,
+    quotes: &['"', '\''],
+};
+
+fn syntax(language: &str) -> Option<&'static Syntax> {
+    match language.trim().to_lowercase().as_str() {
+        "rust" | "rs" => Some(&RUST),
+        "python" | "py" => Some(&PYTHON),
+        "json" => Some(&JSON),
+        "sh" | "bash" | "shell" | "zsh" => Some(&SHELL),
+        _ => None,
+    }
+}
+
+fn starts_with(chars: &[char], i: usize, s: &str) -> bool {
+    let mut j = i;
+    for c in s.chars() {
+        if chars.get(j) != Some(&c) {
+            return false;
+        }
+        j += 1;
+    }
+    true
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Character ranges of the tokens worth colouring, empty for languages we don't know
+pub(super) fn highlight(language: &str, code: &str) -> Vec<(usize, usize, Token)> {
+    let syntax = match syntax(language) {
+        Some(s) => s,
+        None => return vec![],
+    };
+    let chars = code.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i];
+        if syntax.line_comment.map_or(false, |p| starts_with(&chars, i, p)) {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            tokens.push((start, i, Token::Comment));
+        } else if let Some((open, close)) = syntax.block_comment.filter(|(open, _)| starts_with(&chars, i, open)) {
+            i += open.chars().count();
+            while i < chars.len() && !starts_with(&chars, i, close) {
+                i += 1;
+            }
+            i = (i + close.chars().count()).min(chars.len());
+            tokens.push((start, i, Token::Comment));
+        } else if syntax.quotes.contains(&c) {
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            i = (i + 1).min(chars.len());
+            tokens.push((start, i, Token::String));
+        } else if c.is_ascii_digit() {
+            i += 1;
+            while i < chars.len() && (is_word(chars[i]) || chars[i] == '.') {
+                i += 1;
+            }
+            tokens.push((start, i, Token::Number));
+        } else if is_word(c) {
+            while i < chars.len() && is_word(chars[i]) {
+                i += 1;
+            }
+            let word = chars[start..i].iter().collect::<String>();
+            if syntax.keywords.contains(&word.as_str()) {
+                tokens.push((start, i, Token::Keyword));
+            }
+        } else {
+            i += 1;
+        }
+    }
+    tokens
+}
@@ -0,0 +1,87 @@
+use std::{cell::Cell, rc::Rc};
+
+use gtk4::{traits::{BoxExt, WidgetExt}, Align, Label, Orientation, TextView};
+
+use crate::data::text::{ListKind, RichText};
+
+use super::{TextEditor, TextEditingParagraph};
+use super::components::create_p;
+
+const INDENT_WIDTH: i32 = 24;
+const BULLETS: [&str; 3] = ["•", "◦", "▪"];
+
+#[derive(Debug, Clone)]
+pub(super) struct ListItemEditor {
+    pub row: gtk4::Box,
+    pub marker: Label,
+    pub text: TextView,
+    pub kind: ListKind,
+    indent: Rc<Cell<usize>>,
+}
+
+impl ListItemEditor {
+    pub fn indent(&self) -> usize {
+        self.indent.get()
+    }
+
+    pub fn set_indent(&self, indent: usize) {
+        self.indent.set(indent);
+        self.row.set_margin_start(indent as i32 * INDENT_WIDTH);
+    }
+}
+
+pub(super) fn create_list_item(editor: &TextEditor, kind: ListKind, indent: usize, text: RichText) -> ListItemEditor {
+    let text = create_p(editor, text);
+    let marker = Label::builder()
+        .css_classes(["list-marker"])
+        .valign(Align::Start)
+        .build();
+    let row = gtk4::Box::builder()
+        .css_classes(["list-item"])
+        .orientation(Orientation::Horizontal)
+        .hexpand(true)
+        .build();
+    row.append(&marker);
+    row.append(&text);
+    let item = ListItemEditor {
+        row,
+        marker,
+        text,
+        kind,
+        indent: Rc::new(Cell::new(0)),
+    };
+    item.set_indent(indent);
+    item
+}
+
+/// Deepest indent allowed for the item at `i`, one level under the item above it
+pub(super) fn max_indent(paragraphs: &[TextEditingParagraph], i: usize) -> usize {
+    match i.checked_sub(1).map(|i| &paragraphs[i]) {
+        Some(TextEditingParagraph::ListItem(above)) => above.indent() + 1,
+        _ => 0,
+    }
+}
+
+/// Numbers restart whenever a list is interrupted, or a level changes kind
+pub(super) fn update_list_markers(paragraphs: &[TextEditingParagraph]) {
+    let mut counters: Vec<(ListKind, usize)> = Vec::new();
+    for p in paragraphs {
+        if let TextEditingParagraph::ListItem(item) = p {
+            let indent = item.indent();
+            counters.truncate(indent + 1);
+            while counters.len() <= indent {
+                counters.push((item.kind, 0));
+            }
+            if counters[indent].0 != item.kind {
+                counters[indent] = (item.kind, 0);
+            }
+            counters[indent].1 += 1;
+            item.marker.set_text(&match item.kind {
+                ListKind::Bullet => BULLETS[indent % BULLETS.len()].to_string(),
+                ListKind::Numbered => format!("{}.", counters[indent].1),
+            });
+        } else {
+            counters.clear();
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use gtk4::{prelude::*, TextBuffer, TextIter, TextTag, TextView, TextWindowType, UriLauncher, Window, Widget, gio::Cancellable, pango::{Style, Underline}};
+
+use crate::data::text::{RichText, Mark, MarkKind};
+
+const NAMED_MARKS: [MarkKind; 4] = [MarkKind::Bold, MarkKind::Italic, MarkKind::Strikethrough, MarkKind::Code];
+
+fn tag_name(kind: &MarkKind) -> Option<&'static str> {
+    match kind {
+        MarkKind::Bold => Some("bold"),
+        MarkKind::Italic => Some("italic"),
+        MarkKind::Strikethrough => Some("strikethrough"),
+        MarkKind::Code => Some("code"),
+        MarkKind::Link(_) | MarkKind::FileLink(_) => None,
+    }
+}
+
+pub(super) fn create_buffer(text: &RichText) -> TextBuffer {
+    let buffer = TextBuffer::new(None);
+    let tags = buffer.tag_table();
+    tags.add(&TextTag::builder().name("bold").weight(700).build());
+    tags.add(&TextTag::builder().name("italic").style(Style::Italic).build());
+    tags.add(&TextTag::builder().name("strikethrough").strikethrough(true).build());
+    tags.add(&TextTag::builder().name("code").family("monospace").build());
+    buffer.begin_irreversible_action();
+    insert_rich_text(&buffer, 0, text);
+    buffer.end_irreversible_action();
+    buffer
+}
+
+pub(super) fn insert_rich_text(buffer: &TextBuffer, offset: i32, text: &RichText) {
+    buffer.insert(&mut buffer.iter_at_offset(offset), &text.text);
+    for mark in &text.marks {
+        let start = buffer.iter_at_offset(offset + mark.start as i32);
+        let end = buffer.iter_at_offset(offset + mark.end as i32);
+        match &mark.kind {
+            MarkKind::Link(_) | MarkKind::FileLink(_) => apply_link(buffer, &start, &end, mark.kind.clone()),
+            kind => buffer.apply_tag_by_name(tag_name(kind).unwrap(), &start, &end),
+        }
+    }
+}
+
+pub(super) fn read_rich_text(buffer: &TextBuffer, start: &TextIter, end: &TextIter) -> RichText {
+    let tags = buffer.tag_table();
+    let mut marks = Vec::new();
+    for kind in NAMED_MARKS {
+        let tag = tags.lookup(tag_name(&kind).unwrap()).unwrap();
+        for (s, e) in tag_ranges(&tag, start, end) {
+            marks.push(Mark { kind: kind.clone(), start: s, end: e });
+        }
+    }
+    for tag in link_tags(buffer) {
+        let link = link_kind(&tag).unwrap();
+        for (s, e) in tag_ranges(&tag, start, end) {
+            marks.push(Mark { kind: link.clone(), start: s, end: e });
+        }
+    }
+    marks.sort_by_key(|m| m.start);
+    RichText {
+        text: buffer.text(start, end, true).to_string(),
+        marks,
+    }
+}
+
+/// Applies the mark to the selection, or removes it if the whole selection already has it
+pub(super) fn toggle_mark(buffer: &TextBuffer, kind: MarkKind) {
+    if let Some((start, end)) = buffer.selection_bounds() {
+        let tag = buffer.tag_table().lookup(tag_name(&kind).unwrap()).unwrap();
+        let whole = (end.offset() - start.offset()) as usize;
+        if tag_ranges(&tag, &start, &end) == vec![(0, whole)] {
+            buffer.remove_tag(&tag, &start, &end);
+        } else {
+            buffer.apply_tag(&tag, &start, &end);
+        }
+    }
+}
+
+/// Turns the selection into a link when pasting a URL over it, otherwise replaces it like a normal paste
+pub(super) fn paste_over_selection(buffer: &TextBuffer, text: &str) {
+    if let Some((start, end)) = buffer.selection_bounds() {
+        if is_url(text) {
+            apply_link(buffer, &start, &end, MarkKind::Link(text.trim().to_string()));
+        } else {
+            buffer.delete_selection(true, true);
+            buffer.insert_at_cursor(text);
+        }
+    }
+}
+
+/// Inserts `text` at the cursor, linked to `link`
+pub(super) fn insert_link(buffer: &TextBuffer, text: &str, link: MarkKind) {
+    let start = buffer.cursor_position();
+    buffer.insert_at_cursor(text);
+    let end = start + text.chars().count() as i32;
+    apply_link(buffer, &buffer.iter_at_offset(start), &buffer.iter_at_offset(end), link);
+}
+
+pub(super) fn link_at(view: &TextView, x: f64, y: f64) -> Option<MarkKind> {
+    let (x, y) = view.window_to_buffer_coords(TextWindowType::Widget, x as i32, y as i32);
+    let iter = view.iter_at_location(x, y)?;
+    iter.tags().iter().find_map(link_kind)
+}
+
+/// Opens web links in the browser, and file links in a tab
+pub(super) fn follow_link(widget: &impl IsA<Widget>, link: &MarkKind) {
+    match link {
+        MarkKind::Link(href) => open_link(widget, href),
+        MarkKind::FileLink(id) => {
+            let _ = widget.activate_action("win.open-file", Some(&id.to_string().to_variant()));
+        },
+        _ => (),
+    }
+}
+
+pub(super) fn open_link(widget: &impl IsA<Widget>, href: &str) {
+    let window = widget.root().and_downcast::<Window>();
+    UriLauncher::new(href).launch(window.as_ref(), None::<&Cancellable>, |_| ());
+}
+
+/// Links the range to `link`, with one tag for each target, and drops the tags no longer linking anything
+fn apply_link(buffer: &TextBuffer, start: &TextIter, end: &TextIter, link: MarkKind) {
+    let tags = link_tags(buffer);
+    for tag in &tags {
+        buffer.remove_tag(tag, start, end);
+    }
+    let tag = match tags.iter().find(|tag| link_kind(tag).as_ref() == Some(&link)) {
+        Some(tag) => tag.clone(),
+        None => {
+            let tag = TextTag::builder()
+                .underline(Underline::Single)
+                .foreground("#1c71d8")
+                .build();
+            unsafe {
+                tag.set_data("link", link);
+            }
+            buffer.tag_table().add(&tag);
+            tag
+        },
+    };
+    buffer.apply_tag(&tag, start, end);
+    let (first, last) = buffer.bounds();
+    for unused in tags.iter().filter(|t| **t != tag && tag_ranges(t, &first, &last).is_empty()) {
+        buffer.tag_table().remove(unused);
+    }
+}
+
+fn link_tags(buffer: &TextBuffer) -> Vec<TextTag> {
+    let mut tags = Vec::new();
+    buffer.tag_table().foreach(|tag| {
+        if link_kind(tag).is_some() {
+            tags.push(tag.clone());
+        }
+    });
+    tags
+}
+
+fn link_kind(tag: &TextTag) -> Option<MarkKind> {
+    unsafe {
+        tag.data::<MarkKind>("link").map(|link| link.as_ref().clone())
+    }
+}
+
+fn is_url(text: &str) -> bool {
+    let text = text.trim();
+    ["http://", "https://", "mailto:"].iter().any(|scheme| text.starts_with(scheme))
+        && !text.contains(char::is_whitespace)
+}
+
+/// Offsets of the ranges between `start` and `end` that have the tag, relative to `start`
+fn tag_ranges(tag: &TextTag, start: &TextIter, end: &TextIter) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut iter = start.clone();
+    if !iter.has_tag(tag) && !iter.forward_to_tag_toggle(Some(tag)) {
+        return ranges;
+    }
+    while iter.offset() < end.offset() {
+        let s = iter.offset();
+        let more = iter.forward_to_tag_toggle(Some(tag));
+        let e = iter.offset().min(end.offset());
+        if e > s {
+            ranges.push(((s - start.offset()) as usize, (e - start.offset()) as usize));
+        }
+        if !more || !iter.forward_to_tag_toggle(Some(tag)) {
+            break;
+        }
+    }
+    ranges
+}
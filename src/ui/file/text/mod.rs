@@ -1,15 +1,38 @@
 use std::{cell::RefCell, rc::Rc, sync::Mutex};
 
 use glib::Cast;
-use gtk4::{Widget, traits::{BoxExt, EditableExt, TextBufferExt, TextViewExt}, Orientation, Text, gio::Menu, TextView};
+use gtk4::{Widget, traits::{BoxExt, CheckButtonExt, EditableExt, TextBufferExt, TextViewExt}, Orientation, Text, TextView};
 
-use crate::{data::{text::{TextContent, Paragraph}, FileID}, db::{Saveable, DB}};
+use crate::{data::{text::{TextContent, Paragraph, RichText, ListItem, CheckItem, CodeBlock}, FileID}, db::{Saveable, DB}};
 
+use self::asset::{AssetEditor, create_image, create_attachment, create_drop_target};
+use self::check::{CheckItemEditor, create_check_item};
+use self::code::{CodeBlockEditor, create_code_block};
 use self::components::{create_p, create_heading};
+use self::list::{ListItemEditor, create_list_item, update_list_markers};
+use self::marks::read_rich_text;
 
 use super::components::create_header;
 
+mod actions;
+mod asset;
+mod check;
+mod code;
 mod components;
+mod highlight;
+mod list;
+mod marks;
+
+/// Everything the paragraph widgets need to reach the rest of the editor
+#[derive(Clone)]
+struct TextEditor {
+    db: Rc<Mutex<DB>>,
+    id: FileID,
+    /// The file's directory, which attachment paths are relative to
+    dir: String,
+    view: gtk4::Box,
+    content: Rc<RefCell<Vec<TextEditingParagraph>>>,
+}
 
 #[derive(Debug, Clone)]
 struct TextEditingState {
@@ -22,6 +45,35 @@ enum TextEditingParagraph {
     H2(Text),
     H3(Text),
     H4(Text),
+    ListItem(ListItemEditor),
+    Check(CheckItemEditor),
+    Code(CodeBlockEditor),
+    Image(AssetEditor),
+    Attachment(AssetEditor),
+}
+
+impl TextEditingParagraph {
+    fn text_view(&self) -> Option<&TextView> {
+        match self {
+            Self::Text(v) => Some(v),
+            Self::ListItem(item) => Some(&item.text),
+            Self::Check(item) => Some(&item.text),
+            Self::Code(block) => Some(&block.text),
+            _ => None,
+        }
+    }
+
+    /// The widget that sits directly in the editor's box
+    fn widget(&self) -> Widget {
+        match self {
+            Self::Text(v) => v.clone().upcast(),
+            Self::H2(t) | Self::H3(t) | Self::H4(t) => t.clone().upcast(),
+            Self::ListItem(item) => item.row.clone().upcast(),
+            Self::Check(item) => item.row.clone().upcast(),
+            Self::Code(block) => block.row.clone().upcast(),
+            Self::Image(asset) | Self::Attachment(asset) => asset.row.clone().upcast(),
+        }
+    }
 }
 
 impl TextEditingState {
@@ -30,19 +82,43 @@ impl TextEditingState {
             paragraphs: self.content.borrow_mut().iter().map(|p| match p {
                 TextEditingParagraph::Text(p) => {
                     let b = p.buffer();
-                    Paragraph::Text(b.text(&b.start_iter(), &b.end_iter(), true).to_string())
+                    Paragraph::Text(read_rich_text(&b, &b.start_iter(), &b.end_iter()))
                 },
                 TextEditingParagraph::H2(p) => Paragraph::H2(p.text().to_string()),
                 TextEditingParagraph::H3(p) => Paragraph::H3(p.text().to_string()),
                 TextEditingParagraph::H4(p) => Paragraph::H4(p.text().to_string()),
+                TextEditingParagraph::ListItem(item) => {
+                    let b = item.text.buffer();
+                    Paragraph::ListItem(ListItem {
+                        kind: item.kind,
+                        indent: item.indent(),
+                        text: read_rich_text(&b, &b.start_iter(), &b.end_iter()),
+                    })
+                },
+                TextEditingParagraph::Check(item) => {
+                    let b = item.text.buffer();
+                    Paragraph::Check(CheckItem {
+                        checked: item.check.is_active(),
+                        text: read_rich_text(&b, &b.start_iter(), &b.end_iter()),
+                    })
+                },
+                TextEditingParagraph::Code(block) => {
+                    let b = block.text.buffer();
+                    Paragraph::Code(CodeBlock {
+                        language: block.language(),
+                        code: b.text(&b.start_iter(), &b.end_iter(), true).to_string(),
+                    })
+                },
+                TextEditingParagraph::Image(asset) => Paragraph::Image(asset.attachment()),
+                TextEditingParagraph::Attachment(asset) => Paragraph::Attachment(asset.attachment()),
             }).collect()
         }
     }
 }
 
 impl Saveable for TextEditingState {
-    fn save(&self, root: String, id: FileID) {
-        self.make_text_content().save(root, id)
+    fn save(&self, db: &mut DB, id: FileID) {
+        self.make_text_content().save(db, id)
     }
 }
 
@@ -51,17 +127,16 @@ impl Saveable for TextEditingState {
 pub fn display_text<F: Fn(String) + 'static>(
     db: &Rc<Mutex<DB>>,
     id: FileID,
+    dir: String,
     title: &str,
     keywords: Vec<String>,
     mut content: TextContent,
     on_rename: F,
 ) -> (Widget, Box<dyn Saveable>) {
     if content.paragraphs.is_empty() {
-        content.paragraphs.push(Paragraph::Text(String::new()));
+        content.paragraphs.push(Paragraph::Text(RichText::default()));
     }
     
-    let edit_content = Rc::new(RefCell::new(Vec::new()));
-
     let view = gtk4::Box::builder()
         .css_classes(["file-editor", "text-editor"])
         .orientation(Orientation::Vertical)
@@ -71,34 +146,64 @@ pub fn display_text<F: Fn(String) + 'static>(
         .build();
     view.append(&create_header(db, id, title, keywords, on_rename));
 
-    let text_menu = Menu::new();
-    text_menu.append(Some("Into Heading 2"), Some("text.into-heading2"));
-    text_menu.append(Some("Into Heading 3"), Some("text.into-heading3"));
-    text_menu.append(Some("Into Heading 4"), Some("text.into-heading4"));
-    text_menu.append(Some("Into Text"), Some("text.into-text"));
+    let editor = TextEditor {
+        db: db.clone(),
+        id,
+        dir,
+        view: view.clone(),
+        content: Rc::new(RefCell::new(Vec::new())),
+    };
+    view.add_controller(create_drop_target(&editor, &view));
+
     for p in content.paragraphs {
         match p {
             Paragraph::Text(text) => {
-                let v = create_p(&edit_content, &view, text);
+                let v = create_p(&editor, text);
                 view.append(&v);
-                edit_content.borrow_mut().push(TextEditingParagraph::Text(v));
+                editor.content.borrow_mut().push(TextEditingParagraph::Text(v));
             },
             Paragraph::H2(text) => {
-                let p = create_heading(&edit_content, &view, 2, text);
+                let p = create_heading(&editor, 2, text);
                 view.append(&p);
-                edit_content.borrow_mut().push(TextEditingParagraph::H2(p));
+                editor.content.borrow_mut().push(TextEditingParagraph::H2(p));
             },
             Paragraph::H3(text) => {
-                let p = create_heading(&edit_content, &view, 3, text);
+                let p = create_heading(&editor, 3, text);
                 view.append(&p);
-                edit_content.borrow_mut().push(TextEditingParagraph::H3(p));
+                editor.content.borrow_mut().push(TextEditingParagraph::H3(p));
             },
             Paragraph::H4(text) => {
-                let p = create_heading(&edit_content, &view, 4, text);
+                let p = create_heading(&editor, 4, text);
                 view.append(&p);
-                edit_content.borrow_mut().push(TextEditingParagraph::H4(p));
+                editor.content.borrow_mut().push(TextEditingParagraph::H4(p));
+            },
+            Paragraph::ListItem(item) => {
+                let item = create_list_item(&editor, item.kind, item.indent, item.text);
+                view.append(&item.row);
+                editor.content.borrow_mut().push(TextEditingParagraph::ListItem(item));
+            },
+            Paragraph::Check(item) => {
+                let item = create_check_item(&editor, item.checked, item.text);
+                view.append(&item.row);
+                editor.content.borrow_mut().push(TextEditingParagraph::Check(item));
+            },
+            Paragraph::Code(block) => {
+                let block = create_code_block(&editor, block.language, block.code);
+                view.append(&block.row);
+                editor.content.borrow_mut().push(TextEditingParagraph::Code(block));
+            },
+            Paragraph::Image(attachment) => {
+                let asset = create_image(&editor, attachment);
+                view.append(&asset.row);
+                editor.content.borrow_mut().push(TextEditingParagraph::Image(asset));
+            },
+            Paragraph::Attachment(attachment) => {
+                let asset = create_attachment(&editor, attachment);
+                view.append(&asset.row);
+                editor.content.borrow_mut().push(TextEditingParagraph::Attachment(asset));
             },
         }
     }
-    (view.upcast(), Box::new(TextEditingState { content: edit_content }))
+    update_list_markers(&editor.content.borrow());
+    (view.upcast(), Box::new(TextEditingState { content: editor.content }))
 }
\ No newline at end of file
@@ -12,11 +12,13 @@ use gtk4::{Widget, ScrolledWindow, PolicyType, Button, Orientation, Inhibit};
 use gtk4::gio::{ActionEntry, ThemedIcon};
 use gtk4::{prelude::*, glib, HeaderBar};
 
-use self::cmd::search::command_search_window;
+use self::cmd::search::{command_search_window, todo_search_window};
 use self::file::display_file;
+use self::transfer::{open_file_dialog, save_file_dialog, transfer_error_dialog};
 
 mod cmd;
 mod file;
+mod transfer;
 
 const ICON_SPREADSHEET: &str = "x-office-spreadsheet-symbolic";
 const ICON_TEXTDOC: &str = "x-office-document-symbolic";
@@ -56,6 +58,81 @@ pub fn build_ui(app: &Application) {
         }))
         .build();
 
+    let todos = ActionEntry::builder("todos")
+        .activate(clone!(@strong db, @strong ui, @strong app => move |_, _, _| {
+            ui.borrow().save_all(&db);
+            todo_search_window(&db, &app)
+        }))
+        .build();
+
+    let export_markdown = ActionEntry::builder("export-markdown")
+        .activate(clone!(@strong db, @strong ui => move |_, _, _| {
+            let ui = ui.borrow();
+            let (id, title) = match ui.selected_file(&db, Category::Text) {
+                Some(file) => file,
+                None => return,
+            };
+            ui.save_all(&db);
+            let window = ui.window.clone();
+            save_file_dialog(&window, "Export as Markdown", &(title + ".md"), "Markdown", &["md", "markdown"], clone!(@strong db, @strong window => move |path| {
+                let exported = db.lock().unwrap().export_markdown(id, &path);
+                if let Err(e) = exported {
+                    transfer_error_dialog(&window, "Couldn't Export Markdown", &path, e);
+                }
+            }));
+        }))
+        .build();
+
+    let import_markdown = ActionEntry::builder("import-markdown")
+        .activate(clone!(@strong db, @strong ui => move |_, _, _| {
+            let window = ui.borrow().window.clone();
+            open_file_dialog(&window, "Import Markdown", "Markdown", &["md", "markdown"], clone!(@strong db, @strong ui, @strong window => move |path| {
+                let id = db.lock().unwrap().import_markdown(&path);
+                match id {
+                    Ok(id) => UI::open_file(&ui, &db, id),
+                    Err(e) => transfer_error_dialog(&window, "Couldn't Import Markdown", &path, e),
+                }
+            }));
+        }))
+        .build();
+
+    let export_table = ActionEntry::builder("export-table")
+        .parameter_type(Some(VariantTy::STRING))
+        .activate(clone!(@strong db, @strong ui => move |_, _, param| {
+            let extension = param.unwrap().str().unwrap().to_string();
+            let ui = ui.borrow();
+            let (id, title) = match ui.selected_file(&db, Category::Table) {
+                Some(file) => file,
+                None => return,
+            };
+            let (name, delimiter) = match extension.as_str() {
+                "tsv" => ("TSV", '\t'),
+                _ => ("CSV", ','),
+            };
+            ui.save_all(&db);
+            let window = ui.window.clone();
+            save_file_dialog(&window, &format!("Export as {}", name), &format!("{}.{}", title, extension), name, &[extension.as_str()], clone!(@strong db, @strong window => move |path| {
+                let exported = db.lock().unwrap().export_table(id, &path, delimiter);
+                if let Err(e) = exported {
+                    transfer_error_dialog(&window, "Couldn't Export Table", &path, e);
+                }
+            }));
+        }))
+        .build();
+
+    let import_table = ActionEntry::builder("import-table")
+        .activate(clone!(@strong db, @strong ui => move |_, _, _| {
+            let window = ui.borrow().window.clone();
+            open_file_dialog(&window, "Import Table", "CSV or TSV", &["csv", "tsv", "tab", "txt"], clone!(@strong db, @strong ui, @strong window => move |path| {
+                let id = db.lock().unwrap().import_table(&path);
+                match id {
+                    Ok(id) => UI::open_file(&ui, &db, id),
+                    Err(e) => transfer_error_dialog(&window, "Couldn't Import Table", &path, e),
+                }
+            }));
+        }))
+        .build();
+
     ui.borrow_mut().window.connect_close_request(clone!(@strong ui, @strong db => move |_| {
         ui.borrow_mut().save_all(&db);
         Inhibit(false)
@@ -63,7 +140,7 @@ pub fn build_ui(app: &Application) {
 
     let ui = ui.borrow_mut();
     
-    ui.window.add_action_entries([cmd, new_text, new_table, open_file]);
+    ui.window.add_action_entries([cmd, new_text, new_table, open_file, todos, export_markdown, import_markdown, export_table, import_table]);
 
     ui.window.present();
 
@@ -125,7 +202,7 @@ impl UI {
             let saveable = unsafe {
                 page.data::<Box<dyn Saveable>>("saveable").unwrap().as_ref()
             };
-            saveable.save(db.lock().unwrap().root.clone(), *id);
+            saveable.save(&mut db.lock().unwrap(), *id);
             // This is the last one, but isn't removed yet
             if tab_view.n_pages() == 1 {
                 command_search_window(&db, &app, true);
@@ -146,12 +223,16 @@ impl UI {
                 ui.borrow_mut().rename_tab(id, title.as_str());
                 db.lock().unwrap().rename_file(id, title);
             }));
-            let d = db.lock().unwrap();
-            let display = &d.get_file(id).unwrap();
-            uii.open_tab(id, match display.category {
+            // Not kept locked while the tab is added, as that can map the file's widgets
+            let (category, title) = {
+                let d = db.lock().unwrap();
+                let display = d.get_file(id).unwrap();
+                (display.category, display.title.clone())
+            };
+            uii.open_tab(id, match category {
                 Category::Text => ICON_TEXTDOC,
                 Category::Table => ICON_SPREADSHEET,
-            }, display.title.as_str(), &v, s);
+            }, title.as_str(), &v, s);
         }
     }
 
@@ -165,6 +246,21 @@ impl UI {
         }
     }
 
+    /// The id and title of the file in the selected tab, if it's of `category`
+    fn selected_file(&self, db: &Rc<Mutex<DB>>, category: Category) -> Option<(FileID, String)> {
+        let page = self.tab_view.selected_page()?;
+        let id = unsafe {
+            *page.data::<FileID>("id")?.as_ref()
+        };
+        let db = db.lock().unwrap();
+        let display = db.get_file(id)?;
+        if display.category == category {
+            Some((id, display.title.clone()))
+        } else {
+            None
+        }
+    }
+
     fn try_switch_to_tab(&self, id: FileID) -> bool {
         for i in 0..self.tab_view.n_pages() {
             let this_id = unsafe {
@@ -200,7 +296,7 @@ impl UI {
             let saveable = unsafe {
                 page.data::<Box<dyn Saveable>>("saveable").unwrap().as_ref()
             };
-            saveable.save(db.lock().unwrap().root.clone(), *id);
+            saveable.save(&mut db.lock().unwrap(), *id);
         }
     }
 }
\ No newline at end of file
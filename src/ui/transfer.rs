@@ -0,0 +1,62 @@
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use adw::{prelude::*, MessageDialog};
+use gtk4::gio::{Cancellable, ListStore};
+use gtk4::{prelude::*, FileDialog, FileFilter, Window};
+
+fn file_filter(name: &str, suffixes: &[&str]) -> FileFilter {
+    let filter = FileFilter::new();
+    filter.set_name(Some(name));
+    for suffix in suffixes {
+        filter.add_suffix(suffix);
+    }
+    filter
+}
+
+fn file_dialog(title: &str, filter_name: &str, suffixes: &[&str]) -> FileDialog {
+    let filter = file_filter(filter_name, suffixes);
+    let filters = ListStore::new(FileFilter::static_type());
+    filters.append(&filter);
+    FileDialog::builder()
+        .title(title)
+        .modal(true)
+        .filters(&filters)
+        .default_filter(&filter)
+        .build()
+}
+
+/// Asks where to save a file, `on_pick` only runs if the user picks a path
+pub fn save_file_dialog<F: FnOnce(PathBuf) + 'static>(window: &impl IsA<Window>, title: &str, initial_name: &str, filter_name: &str, suffixes: &[&str], on_pick: F) {
+    let dialog = file_dialog(title, filter_name, suffixes);
+    dialog.set_initial_name(Some(initial_name));
+    dialog.save(Some(window), None::<&Cancellable>, move |file| {
+        if let Some(path) = file.ok().and_then(|f| f.path()) {
+            on_pick(path);
+        }
+    });
+}
+
+/// Asks for a file to open, `on_pick` only runs if the user picks one
+pub fn open_file_dialog<F: FnOnce(PathBuf) + 'static>(window: &impl IsA<Window>, title: &str, filter_name: &str, suffixes: &[&str], on_pick: F) {
+    let dialog = file_dialog(title, filter_name, suffixes);
+    dialog.open(Some(window), None::<&Cancellable>, move |file| {
+        if let Some(path) = file.ok().and_then(|f| f.path()) {
+            on_pick(path);
+        }
+    });
+}
+
+/// Tells the user a file couldn't be imported or exported, with why
+pub fn transfer_error_dialog(window: &impl IsA<Window>, heading: &str, path: &Path, error: impl Display) {
+    let dialog = MessageDialog::builder()
+        .heading(heading)
+        .body(format!("{}: {}", path.display(), error))
+        .default_response("close")
+        .close_response("close")
+        .modal(true)
+        .build();
+    dialog.set_transient_for(Some(window));
+    dialog.add_response("close", "_Close");
+    dialog.present();
+}